@@ -0,0 +1,47 @@
+// Ticks a PhysObjManager over a grid of statics with and without the broadphase
+// run with `cargo bench --bench broadphase`
+
+use std::time::Instant;
+
+use burge_proj::{component::Component, element::ElementBase, physics::{box_collider, POMComponent, POMSettings, PhysEvent, PhysObjManager}};
+use uuid::Uuid;
+
+const DYNAMICS: usize = 64;
+const TICKS: usize = 20;
+
+fn ms_per_tick(statics: usize, cell_size: f32) -> f64 {
+    let settings = POMSettings { cell_size: cell_size, ..POMSettings::default() };
+    let mut pom = PhysObjManager::with_settings(settings);
+    let sender = pom.to_any().downcast_ref::<POMComponent>().unwrap().new_sender();
+
+    // square tile level, one unit per tile
+    let width = (statics as f32).sqrt().ceil() as usize;
+    for i in 0..statics {
+        let pos = [(i % width) as f32, -((i / width) as f32)];
+        sender.send(PhysEvent::StaticPO(Uuid::new_v4(), box_collider(pos, [1.0,1.0])));
+    }
+    pom.local_update(1.0);
+    pom.post_update();
+
+    let dynamics: Vec<Uuid> = (0..DYNAMICS).map(|_| Uuid::new_v4()).collect();
+
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        for (i, uuid) in dynamics.iter().enumerate() {
+            let pos = [(i * width / DYNAMICS) as f32 + 0.5, 0.9];
+            sender.send(PhysEvent::DynamicPO(*uuid, box_collider(pos, [0.8,0.8])));
+        }
+        pom.local_update(1.0);
+        pom.post_update();
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / TICKS as f64
+}
+
+fn main() {
+    println!("{:>8} {:>14} {:>14}", "statics", "none (ms/tick)", "grid (ms/tick)");
+    for statics in [100, 1_000, 10_000] {
+        let none = ms_per_tick(statics, 0.0);
+        let grid = ms_per_tick(statics, POMSettings::default().cell_size);
+        println!("{:>8} {:>14.3} {:>14.3}", statics, none, grid);
+    }
+}
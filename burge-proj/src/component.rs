@@ -9,6 +9,10 @@ pub trait Component {
     }
 	fn to_any(&self) -> &dyn std::any::Any;
     fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Box<dyn Component>;
+    /// Like load, but reports problems with `data`. Scenes load components with this
+    fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Box<dyn Component>, Vec<scene::SceneProblem>> { Ok(self.load(data)) }
+    /// Fields to load this back with, the scene adds "name"
+    fn save(&self) -> serde_json::Value { serde_json::Value::Null }
 }
 
 
@@ -31,6 +35,9 @@ impl ComponentManager {
         self.components.insert(name.to_string(), component);
         element
     }
+    pub fn get(&self, name: &str) -> Option<&dyn Component> {
+        self.components.get(name).map(|component| component.as_ref())
+    }
     pub fn access<T: 'static>(&self, name: &'static str, mut function: impl FnMut(&T)) {
         if let Some(component) = self.components.get(name) {
             if let Some(casted) = component.to_any().downcast_ref::<T>() {
@@ -5,9 +5,18 @@ pub trait ElementBase {
 	fn init(&mut self, uuid: uuid::Uuid, components: &ComponentManager) {}
 	fn local_update(&mut self, td: f32) {}
 	fn post_update(&mut self) {}
+	/// When the element's scene becomes current, see SceneEvent
+	fn on_enter(&mut self) {}
+	/// When the element's scene is switched away from or popped
+	fn on_exit(&mut self) {}
 	
 	fn save(&self) -> serde_json::Value { serde_json::Value::Null}
 	fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Element { Element::Null }
+	/// Like load, but reports problems with `data`
+	fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<scene::SceneProblem>> { Ok(self.load(data)) }
+	/// Like try_load, with the components of the scene it's loaded into, so names they define
+	/// (like physics layers) can be checked. Scenes load elements with this
+	fn try_load_in(&self, data: &serde_json::Map<String, serde_json::Value>, components: &ComponentManager) -> Result<Element, Vec<scene::SceneProblem>> { self.try_load(data) }
 }
 
 pub enum Element {
@@ -45,6 +54,30 @@ impl Element {
 		}
 	}
 
+	pub fn on_enter(&mut self) {
+		match self {
+			Element::Generic(element) => element.on_enter(),
+			Element::Entity(element) => element.on_enter(),
+			Element::Camera(element) => element.on_enter(),
+			_ => ()
+		}
+	}
+	pub fn on_exit(&mut self) {
+		match self {
+			Element::Generic(element) => element.on_exit(),
+			Element::Entity(element) => element.on_exit(),
+			Element::Camera(element) => element.on_exit(),
+			_ => ()
+		}
+	}
+	pub fn save(&self) -> serde_json::Value {
+		match self {
+			Element::Generic(element) => element.save(),
+			Element::Entity(element) => element.save(),
+			Element::Camera(element) => element.save(),
+			_ => serde_json::Value::Null
+		}
+	}
 	pub fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Element {
 		match self {
 			Element::Generic(element) => element.load(data),
@@ -53,6 +86,22 @@ impl Element {
 			_ => Element::Null
 		}
 	}
+	pub fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<scene::SceneProblem>> {
+		match self {
+			Element::Generic(element) => element.try_load(data),
+			Element::Entity(element) => element.try_load(data),
+			Element::Camera(element) => element.try_load(data),
+			_ => Ok(Element::Null)
+		}
+	}
+	pub fn try_load_in(&self, data: &serde_json::Map<String, serde_json::Value>, components: &ComponentManager) -> Result<Element, Vec<scene::SceneProblem>> {
+		match self {
+			Element::Generic(element) => element.try_load_in(data, components),
+			Element::Entity(element) => element.try_load_in(data, components),
+			Element::Camera(element) => element.try_load_in(data, components),
+			_ => Ok(Element::Null)
+		}
+	}
 }
 
 
@@ -91,8 +140,33 @@ impl DefaultCamera {
 }
 
 impl ElementBase for DefaultCamera {
+	fn save(&self) -> serde_json::Value {
+		serde_json::json!({ "pos": self.pos, "scale": self.scale })
+	}
+	/// Skips anything invalid, try_load reports it
 	fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Element {
-		Element::Camera(Box::new(self.clone()))
+		Element::Camera(Box::new(self.read(data, &mut Vec::new())))
+	}
+	fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<scene::SceneProblem>> {
+		let mut problems = Vec::new();
+		let camera = self.read(data, &mut problems);
+		if !problems.is_empty() {
+			return Err(problems);
+		}
+		Ok(Element::Camera(Box::new(camera)))
+	}
+}
+
+impl DefaultCamera {
+	fn read(&self, data: &serde_json::Map<String, serde_json::Value>, problems: &mut Vec<scene::SceneProblem>) -> Self {
+		let mut camera = self.clone();
+		if let Some(pos) = scene::vec2_field(data, "pos", problems) {
+			camera.pos = pos;
+		}
+		if let Some(scale) = scene::f32_field(data, "scale", problems) {
+			camera.scale = scale;
+		}
+		camera
 	}
 }
 impl Camera for DefaultCamera {
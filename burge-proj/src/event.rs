@@ -1,9 +1,10 @@
 use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::cell::{Cell, RefCell};
 use uuid::Uuid;
 
 use crate::component::Component;
+use crate::instance::InputEvent;
 use crate::physics::PhysEvent;
 
 
@@ -33,31 +34,75 @@ impl<T:Clone> Receiver<T> {
 
 #[derive(Clone, Default)]
 pub struct Sender<T:Clone> {
-    receivers: Rc<RefCell<Vec<Rc<Receiver<T>>>>>,
+    channel: Rc<Channel<T>>
+}
 
+#[derive(Default)]
+struct Channel<T:Clone> {
+    receivers: RefCell<Vec<Rc<Receiver<T>>>>,
+    /// Channels of senders made with child, dropped with them
+    children: RefCell<Vec<Weak<Channel<T>>>>,
+    paused: Cell<bool>
 }
 
 impl<T:Clone> Sender<T> {
     pub fn new() -> Self {
         Self {
-            receivers: Rc::new(RefCell::new(Vec::new()))
+            channel: Rc::new(Channel {
+                receivers: RefCell::new(Vec::new()),
+                children: RefCell::new(Vec::new()),
+                paused: Cell::new(false)
+            })
         }
     }
     pub fn new_receiver(&self) -> Rc<Receiver<T>> {
         let r = Receiver::new();
-        self.receivers.borrow_mut().push(r.clone());
+        self.channel.receivers.borrow_mut().push(r.clone());
         r
     }
     pub fn send(&self, event: T) {
+        self.channel.send(&event)
+    }
+    /// Sender that is also sent everything this one is, while it's kept
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+        self.channel.children.borrow_mut().push(Rc::downgrade(&child.channel));
+        child
+    }
+    /// A paused sender drops everything sent to it, its children included
+    pub fn set_paused(&self, paused: bool) {
+        self.channel.paused.set(paused);
+    }
+}
+
+impl<T:Clone> Channel<T> {
+    fn send(&self, event: &T) {
+        if self.paused.get() {
+            return;
+        }
         for r in self.receivers.borrow().iter() {
             r.receive(event.clone())
         }
+
+        let children: Vec<Rc<Channel<T>>> = {
+            let mut children = self.children.borrow_mut();
+            children.retain(|c| c.strong_count() > 0);
+            children.iter().filter_map(|c| c.upgrade()).collect()
+        };
+        for child in children {
+            child.send(event);
+        }
     }
 }
 
 impl<T:Clone + 'static> Component for Sender<T> {
+    /// Each scene gets its own child of the input sender, so pausing a scene only drops its input.
+    /// Other senders are shared
     fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Box<dyn Component> {
-        Box::new(self.clone())
+        match self.to_any().downcast_ref::<Sender<InputEvent>>() {
+            Some(input) => Box::new(input.child()),
+            None => Box::new(self.clone())
+        }
     }
     fn name(&self) -> &'static str {
         "input"
@@ -102,7 +147,7 @@ impl<T:Clone> Router<T> {
         (uuid, r)
     }
 	pub fn new_receiver(&self) -> (Uuid, Rc<Receiver<T>>) {
-        let uuid = Uuid::new_v4();
+        let uuid = crate::ids::new_uuid();
         let r = Receiver::new();
         self.receivers.borrow_mut().insert(uuid, r.clone());
         (uuid, r)
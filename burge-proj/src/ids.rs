@@ -0,0 +1,42 @@
+use std::cell::Cell;
+
+use uuid::Uuid;
+
+
+thread_local! {
+    /// splitmix64 state, None for random uuids
+    static STATE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Makes new_uuid return the same sequence every run with the same seed,
+/// for replays and lockstep. Seed before loading scenes
+pub fn seed(seed: u64) {
+    STATE.with(|state| state.set(Some(seed)));
+}
+
+/// Goes back to random uuids
+pub fn unseed() {
+    STATE.with(|state| state.set(None));
+}
+
+/// Uuid for a new element, receiver or physics object
+pub fn new_uuid() -> Uuid {
+    STATE.with(|state| match state.get() {
+        Some(mut s) => {
+            let mut bytes = [0u8; 16];
+            bytes[..8].copy_from_slice(&splitmix(&mut s).to_le_bytes());
+            bytes[8..].copy_from_slice(&splitmix(&mut s).to_le_bytes());
+            state.set(Some(s));
+            uuid::Builder::from_random_bytes(bytes).into_uuid()
+        },
+        None => Uuid::new_v4()
+    })
+}
+
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
@@ -1,8 +1,8 @@
 use std::{ops::Deref, path, time::{Duration, Instant}};
-use crate::{element::Entity, event::Sender, scene::SceneManager, sprite::SpriteSheet, Vertex
+use crate::{event::Sender, scene::{Scene, SceneManager, Transition}, sprite::SpriteSheet, DebugVertex, Vertex
 };
 use std::path::Path;
-use glium::{glutin::{dpi::PhysicalSize, event::{ElementState, KeyboardInput}, event_loop::ControlFlow}, implement_vertex, texture, Surface, Texture2d};
+use glium::{glutin::{dpi::PhysicalSize, event::{ElementState, KeyboardInput, VirtualKeyCode}, event_loop::ControlFlow}, implement_vertex, texture, Surface, Texture2d};
 use std::collections::HashMap;
 
 use std::rc::Rc;
@@ -28,6 +28,14 @@ pub struct Instance {
     sprite_sheet: SpriteSheet,
 
     pub ss_path: &'static str,
+    /// Where the current scene is written as JSON when the window is closed, nothing is saved if empty
+    pub save_path: &'static str,
+
+    /// Scene updates per second, each update is given a td of 60.0 / tick_rate
+    pub tick_rate: f32,
+
+    /// Draws physics outlines, velocities, contacts and broadphase cells over the scene, toggled with F3
+    pub debug_draw: bool,
 
 
     input: Sender<InputEvent>
@@ -43,7 +51,11 @@ impl Instance {
             fullscreen: false,
             sprite_sheet: SpriteSheet::new((8,8)),
             ss_path: "",
+            save_path: "",
+
+            tick_rate: 60.0,
 
+            debug_draw: false,
 
             input: Sender::new()
         }
@@ -86,6 +98,21 @@ impl Instance {
             },
         ).unwrap();
 
+        let debug_program = glium::Program::new(
+            &display,
+            glium::program::ProgramCreationInput::SourceCode {
+                vertex_shader: include_str!("shaders/debug_vertex.glsl"),
+                tessellation_control_shader: None,
+                tessellation_evaluation_shader: None,
+                geometry_shader: None,
+                fragment_shader: include_str!("shaders/debug_fragment.glsl"),
+                transform_feedback_varyings: None,
+                outputs_srgb: true,
+                uses_point_size: false,
+            },
+        ).unwrap();
+        let debug_indices = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+
 
         let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
         use glium::uniforms::*;
@@ -113,30 +140,39 @@ impl Instance {
         let texture = glium::Texture2d::new(&display, image).unwrap();
         self.sprite_sheet.tex = Some(texture);
 
-        for (_, scene) in self.scene_manager.scenes.iter_mut() {
-            scene.init_elements();
-        }
+        self.scene_manager.init_scenes();
+        self.scene_manager.current_scene().enter();
 
         //let static_buffer = glium::VertexBuffer::new(&display, &self.scene_manager.current_scene().static_sprites).unwrap();
 
 
-        let mut time_delta: f32 = 0.0;
+        let step = 1.0 / self.tick_rate;
+        let mut last_time = Instant::now();
+        let mut accumulator: f32 = 0.0;
         
         let _ = event_loop.run(move |event, _, control_flow | {
-            let start_time = Instant::now();
             *control_flow = ControlFlow::Poll;
             //*control_flow = ControlFlow::WaitUntil(Instant::now()+Duration::from_millis((unsafe { 1.0 / crate::FRAME_RATE }*1000.0 - time_delta*1000.0) as u64));
             match event {
                 glutin::event::Event::WindowEvent { event, .. } => match event {
                     glutin::event::WindowEvent::CloseRequested => {
-                        //self.scene_controller.save();
+                        if !self.save_path.is_empty() {
+                            if let Some(data) = self.scene_manager.save_current_scene() {
+                                if let Err(e) = std::fs::write(self.save_path, serde_json::to_string_pretty(&data).unwrap()) {
+                                    println!("Couldn't save scene to '{}': {}", self.save_path, e);
+                                }
+                            }
+                        }
                         *control_flow = ControlFlow::Exit;
                     },
                     glutin::event::WindowEvent::Resized(size) => {
                         window_size = size;
                     },
-                    glutin::event::WindowEvent::KeyboardInput { input: KeyboardInput {scancode, state, ..}, .. } => {
-                        
+                    glutin::event::WindowEvent::KeyboardInput { input: KeyboardInput {scancode, state, virtual_keycode, ..}, .. } => {
+                        if let (ElementState::Pressed, Some(VirtualKeyCode::F3)) = (state, virtual_keycode) {
+                            self.debug_draw = !self.debug_draw;
+                        }
+
                         match state {
                             ElementState::Pressed => self.input.send(InputEvent::KeyDown(scancode)),
                             ElementState::Released => self.input.send(InputEvent::KeyUp(scancode)),
@@ -153,40 +189,83 @@ impl Instance {
 
                     
 
-                    let scene = self.scene_manager.current_scene();
-
-                    
-                    let vertices = scene.display(&self.sprite_sheet);
+                    let transition = self.scene_manager.transition();
+                    let mut draw_scene = |scene: &Scene, alpha: f32, interpolation: f32| {
+                        let vertices = scene.display(&self.sprite_sheet, interpolation);
+                        let vertex_buffer: glium::VertexBuffer<Vertex> = glium::VertexBuffer::new(&display, &vertices).unwrap();
+
+                        let (ortho_mat, translation) = scene.camera_projection(window_size.into());
+                        let uniforms = glium::uniform! {
+                            ortho_mat: ortho_mat,
+                            translation: translation,
+                            alpha: alpha,
+                            tex: glium::uniforms::Sampler(self.sprite_sheet.tex.as_ref().unwrap(), sampler_behavior)
+                        };
+                        target.draw(&vertex_buffer, indices, &program, &uniforms, &draw_parameters).unwrap();
+                    };
 
-                    let vertex_buffer: glium::VertexBuffer<Vertex> = glium::VertexBuffer::new(&display, &vertices).unwrap();
+                    // overlays like HUDs and pause menus go on top of the scenes below them,
+                    // frozen scenes are drawn where they stopped
+                    for (scene, alpha, running) in self.scene_manager.scenes_to_draw() {
+                        draw_scene(scene, alpha, if running { accumulator / step } else { 1.0 });
+                    }
+                    let scene = self.scene_manager.current_scene();
+                    //target.draw(&static_buffer, &indices, &program, &uniforms, &draw_parameters).unwrap();
 
-                    
-                    
                     let (ortho_mat, translation) = scene.camera_projection(window_size.into());
-                    let uniforms = glium::uniform! {
-                        ortho_mat: ortho_mat,
-                        translation: translation,
-                        tex: glium::uniforms::Sampler(self.sprite_sheet.tex.as_ref().unwrap(), sampler_behavior)
-                    };
-            
-                    target.draw(&vertex_buffer, &indices, &program, &uniforms, &draw_parameters).unwrap();
-                    //target.draw(&static_buffer, &indices, &program, &uniforms, &draw_parameters).unwrap();
+
+                    if self.debug_draw {
+                        let debug_vertices = scene.debug_display();
+                        if !debug_vertices.is_empty() {
+                            let debug_buffer: glium::VertexBuffer<DebugVertex> = glium::VertexBuffer::new(&display, &debug_vertices).unwrap();
+                            let debug_uniforms = glium::uniform! {
+                                ortho_mat: ortho_mat,
+                                translation: translation
+                            };
+                            target.draw(&debug_buffer, debug_indices, &debug_program, &debug_uniforms, &draw_parameters).unwrap();
+                        }
+                    }
+
+                    // fades cover the screen in the background colour, fully at the halfway point
+                    if let Some((Transition::Fade(_), progress)) = transition {
+                        let (r, g, b, _) = self.bg_color;
+                        let color = [r, g, b, 1.0 - (2.0 * progress - 1.0).abs()];
+                        let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+                        let fade_vertices: Vec<DebugVertex> = corners.iter().map(|pos| DebugVertex { pos: *pos, color: color }).collect();
+                        let fade_buffer: glium::VertexBuffer<DebugVertex> = glium::VertexBuffer::new(&display, &fade_vertices).unwrap();
+                        let fade_uniforms = glium::uniform! {
+                            ortho_mat: [[1.0,0.0,0.0],[0.0,1.0,0.0],[0.0,0.0,1.0f32]],
+                            translation: [0.0, 0.0f32]
+                        };
+                        target.draw(&fade_buffer, indices, &debug_program, &fade_uniforms, &draw_parameters).unwrap();
+                    }
                     
                     
                     
                     target.finish().unwrap();
                 },
                 glutin::event::Event::MainEventsCleared => {
-                    
+                    let now = Instant::now();
+                    // after a long stall, drop the time instead of running every missed update
+                    accumulator += (now - last_time).as_secs_f32().min(MAX_FRAME_TIME);
+                    last_time = now;
+
+                    while accumulator >= step {
+                        if let Err(errors) = self.scene_manager.update(60.0 * step, step) {
+                            for e in errors {
+                                println!("{}", e);
+                            }
+                        }
+                        accumulator -= step;
+                    }
+
+                    display.gl_window().window().request_redraw();
                 }
                 _ => ()
             }
-
-            self.scene_manager.current_scene().update_elements(time_delta);
-            display.gl_window().window().request_redraw();
-
-            let end_time = Instant::now();
-            time_delta = (end_time-start_time).as_secs_f32()*60.0;
         });
     }
-}
\ No newline at end of file
+}
+
+/// Longest frame time (in seconds) that gets simulated
+const MAX_FRAME_TIME: f32 = 0.25;
\ No newline at end of file
@@ -6,6 +6,7 @@ pub mod event;
 pub mod scene;
 
 pub mod physics;
+pub mod ids;
 pub mod macros;
 pub mod sprite;
 
@@ -19,4 +20,15 @@ pub struct Vertex {
 }
 
 
-glium::implement_vertex!(Vertex, pos, tex_coords);
\ No newline at end of file
+glium::implement_vertex!(Vertex, pos, tex_coords);
+
+
+/// Vertex of the debug overlay, drawn as lines
+#[derive(Clone, Copy)]
+pub struct DebugVertex {
+    pub pos: [f32;2],
+    pub color: [f32;4]
+}
+
+
+glium::implement_vertex!(DebugVertex, pos, color);
\ No newline at end of file
@@ -1,18 +1,21 @@
 #[macro_export]
+/// `Some([x, y])` from a JSON array of exactly two numbers, otherwise None
 macro_rules! try_vec2 {
     ($value:tt) => {
-        if let serde_json::Value::Array(vec2) = $value {
-            let mut ret = [0.0,0.0];
-            for i in 0..2 {
-                if let Value::Number(num) = &vec2[i] {
-                    if let Some(num) = num.as_f64() {
-                        ret[i] = num as f32;
-                    }
-                }
-            }
-            Some(ret)
-        } else {
-            None
+        match $value {
+            serde_json::Value::Array(vec2) if vec2.len() == 2 => match (vec2[0].as_f64(), vec2[1].as_f64()) {
+                (Some(x), Some(y)) => Some([x as f32, y as f32]),
+                _ => None
+            },
+            _ => None
         }
     };
+}
+
+#[macro_export]
+/// `Some(uuid)` from a JSON string holding a uuid, otherwise None
+macro_rules! try_uuid {
+    ($value:tt) => {
+        $value.as_str().and_then(|v| uuid::Uuid::parse_str(v).ok())
+    };
 }
\ No newline at end of file
@@ -1,6 +1,5 @@
-use burge_proj::{component::{Component, ComponentManager}, element::{DefaultCamera, Element, ElementBase, Entity}, event::{Receiver, RoutedEvent, Sender}, instance::{InputEvent, Instance}, physics::{self, box_collider, POMComponent, PhysEvent, PhysObj, PhysObjManager}, scene::{JSONManager, SceneManager}, sprite::{Sprite, SpriteSheet}, try_vec2, Vertex};
+use burge_proj::{component::{Component, ComponentManager}, element::{DefaultCamera, Element, ElementBase, Entity}, event::{Receiver, RoutedEvent, Sender}, instance::{InputEvent, Instance}, physics::{self, box_collider, POMComponent, POSettings, PhysEvent, PhysObj, PhysObjManager}, scene::{object_field, vec2_field, JSONManager, SceneManager, SceneProblem}, sprite::{Sprite, SpriteSheet}, Vertex};
 use serde_json::Value;
-use uuid::Uuid;
 use std::{default, rc::Rc};
 
 
@@ -13,11 +12,18 @@ struct Player {
 
     uuid: uuid::Uuid,
     
-    hb: PhysObj
+    hb: PhysObj,
+    /// "physics" JSON, saved as it was if the player never got a pom
+    physics: serde_json::Map<String, serde_json::Value>,
+    /// For saving the settings with layer and behaviour names
+    pom: Option<POMComponent>
 }
 
 impl Player {
     pub fn new(pos: [f32;2]) -> Self {
+        let mut hb = box_collider(pos, [1.0,1.0]);
+        hb.settings.gravity_strength = 0.0;
+
         Self {
             phys_receiver: Receiver::new(),
             phys_sender: Sender::new(),
@@ -27,9 +33,28 @@ impl Player {
             uuid: uuid::Uuid::nil(),
             
             
-            hb: box_collider(pos, [1.0,1.0])
+            hb: hb,
+            physics: serde_json::Map::new(),
+            pom: None
         }
     }
+    fn read(&self, data: &serde_json::Map<String, serde_json::Value>, pom: Option<&POMComponent>) -> Result<Element, Vec<SceneProblem>> {
+        let mut default = self.clone();
+        let mut problems = Vec::new();
+        if let Some(pos) = vec2_field(data, "pos", &mut problems) {
+            default.hb.pos = pos;
+        }
+        if let Some(physics) = object_field(data, "physics", &mut problems) {
+            if let Some(settings) = physics_settings(&default.hb.settings, physics, pom, &mut problems) {
+                default.hb.settings = settings;
+            }
+            default.physics = physics.clone();
+        }
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Element::Entity(Box::new(default)))
+    }
 }
 
 impl ElementBase for Player {
@@ -38,23 +63,29 @@ impl ElementBase for Player {
         components.access("pom", |pom: &POMComponent| {
             self.phys_sender = pom.new_sender();
             (_, self.phys_receiver) = pom.new_receiver_uuid(uuid);
+            self.pom = Some(pom.clone());
         });
 
         components.access("input", |input: &Sender<InputEvent>| {
             self.input_receiver = input.new_receiver();
         });
 
-        self.hb.settings.gravity_strength = 0.0;
         self.hb.settings.on_collision = |a, b| {
             vec![]
         };
     }
-    fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Element {
-        let mut default = self.clone();
-        if let Some(pos) = data.get("pos") {
-            default.hb.pos = try_vec2!(pos).unwrap();
-        }
-        Element::Entity(Box::new(default))
+    fn save(&self) -> Value {
+        let physics = match &self.pom {
+            Some(pom) => pom.save_settings(&self.hb.settings),
+            None => Value::Object(self.physics.clone())
+        };
+        serde_json::json!({ "pos": self.hb.pos, "physics": physics })
+    }
+    fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<SceneProblem>> {
+        self.read(data, None)
+    }
+    fn try_load_in(&self, data: &serde_json::Map<String, serde_json::Value>, components: &ComponentManager) -> Result<Element, Vec<SceneProblem>> {
+        self.read(data, scene_pom(components).as_ref())
     }
     fn local_update(&mut self, td: f32) {
         for e in self.phys_receiver.poll() {
@@ -109,28 +140,54 @@ impl Entity for Player {
 pub struct Block {
     pos: [f32;2],
     shape: [f32;2],
+    /// "physics" JSON, saved as it was so layer and behaviour names are kept
+    physics: serde_json::Map<String, serde_json::Value>,
+    settings: POSettings,
 
     phys_sender: Sender<PhysEvent>,
 }
 
 impl ElementBase for Block {
-    fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Element {
-        let mut default = self.clone();
-        if let Some(pos) = data.get("pos") {
-            default.pos = try_vec2!(pos).unwrap();
-        }
-        if let Some(shape) = data.get("shape") {
-            default.shape = try_vec2!(shape).unwrap();
-        }
-        Element::Entity(Box::new(default))
+    fn save(&self) -> Value {
+        serde_json::json!({ "pos": self.pos, "shape": self.shape, "physics": self.physics })
+    }
+    fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<SceneProblem>> {
+        self.read(data, None)
+    }
+    fn try_load_in(&self, data: &serde_json::Map<String, serde_json::Value>, components: &ComponentManager) -> Result<Element, Vec<SceneProblem>> {
+        self.read(data, scene_pom(components).as_ref())
     }
     fn init(&mut self, uuid: uuid::Uuid, components: &ComponentManager) {
+        let mut collider = box_collider(self.pos, self.shape);
+        collider.settings = self.settings;
         components.access("pom", |pom: &POMComponent| {
             self.phys_sender = pom.new_sender();
         });
 
 
-        self.phys_sender.send(PhysEvent::StaticPO(Uuid::nil(), box_collider(self.pos, self.shape)))
+        self.phys_sender.send(PhysEvent::StaticPO(uuid, collider))
+    }
+}
+impl Block {
+    fn read(&self, data: &serde_json::Map<String, serde_json::Value>, pom: Option<&POMComponent>) -> Result<Element, Vec<SceneProblem>> {
+        let mut default = self.clone();
+        let mut problems = Vec::new();
+        if let Some(pos) = vec2_field(data, "pos", &mut problems) {
+            default.pos = pos;
+        }
+        if let Some(shape) = vec2_field(data, "shape", &mut problems) {
+            default.shape = shape;
+        }
+        if let Some(physics) = object_field(data, "physics", &mut problems) {
+            if let Some(settings) = physics_settings(&default.settings, physics, pom, &mut problems) {
+                default.settings = settings;
+            }
+            default.physics = physics.clone();
+        }
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Element::Entity(Box::new(default)))
     }
 }
 impl Entity for Block {
@@ -140,6 +197,29 @@ impl Entity for Block {
 }
 
 
+/// The pom of the scene being loaded
+fn scene_pom(components: &ComponentManager) -> Option<POMComponent> {
+    let mut found = None;
+    components.access("pom", |pom: &POMComponent| found = Some(pom.clone()));
+    found
+}
+
+/// `settings` with the "physics" JSON of an element applied, using the layer and behaviour names of `pom` if there is one
+fn physics_settings(settings: &POSettings, physics: &serde_json::Map<String, serde_json::Value>, pom: Option<&POMComponent>, problems: &mut Vec<SceneProblem>) -> Option<POSettings> {
+    let loaded = match pom {
+        Some(pom) => pom.load_settings(settings, physics),
+        None => settings.try_load(physics)
+    };
+    match loaded {
+        Ok(settings) => Some(settings),
+        Err(errors) => {
+            problems.extend(errors.into_iter().map(|p| p.within("physics")));
+            None
+        }
+    }
+}
+
+
 fn main() {
     let mut instance = Instance::new();
     instance.ss_path = "./src/spritesheet.png";
@@ -154,20 +234,11 @@ fn main() {
 
     instance.scene_manager().json_manager = json_manager;
 
-    let data = r#"{
-        "name": "main",
-        "components": [
-            {"name": "input"}
-        ],
-        "elements": [
-            {"name":"player", "pos": [0,1.5]},
-            {"name":"block", "pos": [-2,-2], "shape": [3,1]}
-        ]
-    }"#;
-    
-    let data: serde_json::Value = serde_json::from_str(data).unwrap();
-
-    instance.scene_manager().add_scene(data);
+    if let Err(errors) = instance.scene_manager().add_scenes_from_dir("./scenes") {
+        for e in errors {
+            println!("{}", e);
+        }
+    }
     instance.scene_manager().set_scene("main");
 
 
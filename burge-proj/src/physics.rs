@@ -1,19 +1,133 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::rc::Rc;
 
 use uuid::Uuid;
 
 use crate::{component::Component, element::{Element, ElementBase}, event::{Sender, Receiver, RoutedEvent, Router}};
+use crate::scene::{bool_field, f32_field, object_field, uuid_field, vec2_field, SceneProblem};
+
+mod broadphase;
+use broadphase::SpatialHash;
+
+mod shape;
+use shape::{add, dot, length, normalize, penetration, scale, sub, Core};
+pub use shape::{Collider, ConvexPolygon, MAX_POLYGON_VERTS};
+
+mod query;
+use query::time_of_impact;
+pub use query::{QueryFilter, RayHit};
+
+mod joint;
+use joint::solve_joints;
+pub use joint::{Joint, JointKind};
+
+mod kinematic;
+use kinematic::Kinematic;
+pub use kinematic::{KinematicPath, MAX_PATH_POINTS};
+
+mod behaviour;
+pub use behaviour::{Behaviour, Behaviours, Contact};
+
+mod debug;
+pub use debug::DebugLine;
+
+mod snapshot;
+pub use snapshot::PhysSnapshot;
+
+mod field;
+pub use field::{FieldKind, ForceField};
+
+mod contact;
+pub use contact::{ContactSummary, MAX_CONTACT_NORMALS};
 
 #[derive(Copy,Clone)]
 pub struct PhysObj {
 	pub pos: [f32;2],
 	pub shape: [f32;2],
 	pub delta: [f32;2],
+	pub collider: Collider,
 	pub settings: POSettings
 }
 
 impl PhysObj {
+    /// Everything but the callbacks, in the format read by load
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::json!({
+            "pos": self.pos,
+            "shape": self.shape,
+            "delta": self.delta,
+            "collider": self.collider.save(),
+            "settings": self.settings.save()
+        })
+    }
+    /// Object from JSON, "pos" and "shape" are required and callbacks are left as the defaults
+    pub fn load(data: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let required = |key: &str, problems: &mut Vec<SceneProblem>| {
+            if !data.contains_key(key) {
+                problems.push(SceneProblem::new(key, "missing"));
+            }
+            vec2_field(data, key, problems)
+        };
+        let pos = required("pos", &mut problems);
+        let shape = required("shape", &mut problems);
+        let delta = vec2_field(data, "delta", &mut problems);
+        let collider = match object_field(data, "collider", &mut problems).map(Collider::try_load) {
+            Some(Ok(collider)) => Some(collider),
+            Some(Err(errors)) => {
+                problems.extend(errors.into_iter().map(|p| p.within("collider")));
+                None
+            },
+            None => None
+        };
+        let settings = match object_field(data, "settings", &mut problems).map(|settings| POSettings::default().try_load(settings)) {
+            Some(Ok(settings)) => Some(settings),
+            Some(Err(errors)) => {
+                problems.extend(errors.into_iter().map(|p| p.within("settings")));
+                None
+            },
+            None => None
+        };
+
+        match (pos, shape) {
+            (Some(pos), Some(shape)) if problems.is_empty() => {
+                let mut po = box_collider(pos, shape);
+                po.delta = delta.unwrap_or([0.0, 0.0]);
+                po.collider = collider.unwrap_or(Collider::Aabb);
+                po.settings = settings.unwrap_or(po.settings);
+                Ok(po)
+            },
+            _ => Err(problems)
+        }
+    }
+    /// Whether the layers and masks of both objects let them interact
+    pub fn interacts_with(&self, other: &PhysObj) -> bool {
+        self.settings.mask & other.settings.layer != 0 && other.settings.mask & self.settings.layer != 0
+    }
+    /// Bounding box after delta is applied, as (min, max)
+    pub fn bounds(&self) -> ([f32;2],[f32;2]) {
+        if let Collider::Aabb = self.collider {
+            let x = self.pos[0] + self.delta[0];
+            let y = self.pos[1] + self.delta[1];
+            ([x, y], [x + self.shape[0], y + self.shape[1]])
+        } else {
+            self.core().bounds()
+        }
+    }
+    /// Collider in world space, after delta is applied
+    fn core(&self) -> Core {
+        Core::new(&self.collider, [self.pos[0] + self.delta[0], self.pos[1] + self.delta[1]], self.shape)
+    }
     pub fn intersects(&self, other: &PhysObj) -> bool {
+        if !matches!((self.collider, other.collider), (Collider::Aabb, Collider::Aabb)) {
+            return penetration(&self.core(), &other.core()).is_some();
+        }
+
         let self_x = self.pos[0] + self.delta[0];
         let self_y = self.pos[1] + self.delta[1];
 
@@ -34,7 +148,12 @@ impl PhysObj {
 
         !(this_left > other_right || this_right < other_left || this_bottom > other_top || this_top < other_bottom)
     }
+    /// Smallest translation that moves self out of other
     pub fn exclusive_delta(&self, other: &Self) -> [f32;2] {
+        if !matches!((self.collider, other.collider), (Collider::Aabb, Collider::Aabb)) {
+            return penetration(&self.core(), &other.core()).unwrap_or([0.0, 0.0]);
+        }
+
         let self_x = self.pos[0] + self.delta[0];
         let self_y = self.pos[1] + self.delta[1];
 
@@ -60,7 +179,7 @@ impl PhysObj {
             down
         };
 
-        if horizontal < vertical {
+        if horizontal.abs() < vertical.abs() {
             [horizontal, 0.0]
         } else {
             [0.0, vertical]
@@ -69,6 +188,48 @@ impl PhysObj {
 }
 
 
+/// Side of an object that was blocked by a solid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom
+}
+
+impl Side {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+            Side::Top => "top",
+            Side::Bottom => "bottom"
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Side::Left),
+            "right" => Some(Side::Right),
+            "top" => Some(Side::Top),
+            "bottom" => Some(Side::Bottom),
+            _ => None
+        }
+    }
+    /// Side that was hit, given the push used to separate the object
+    pub fn from_push(push: [f32;2]) -> Option<Side> {
+        if push[0].abs() > push[1].abs() {
+            if push[0] > 0.0 { Some(Side::Left) } else { Some(Side::Right) }
+        } else if push[1] > 0.0 {
+            Some(Side::Bottom)
+        } else if push[1] < 0.0 {
+            Some(Side::Top)
+        } else {
+            None
+        }
+    }
+}
+
+
 
 
 pub fn box_collider(pos: [f32;2], shape: [f32;2]) -> PhysObj {
@@ -76,16 +237,71 @@ pub fn box_collider(pos: [f32;2], shape: [f32;2]) -> PhysObj {
         pos: pos,
         shape: shape,
         delta: [0.0,0.0],
+        collider: Collider::Aabb,
         settings: POSettings::default()
     }
 }
 
+pub fn circle_collider(pos: [f32;2], radius: f32) -> PhysObj {
+    PhysObj {
+        collider: Collider::Circle { radius: radius },
+        ..box_collider(pos, [2.0*radius, 2.0*radius])
+    }
+}
+
+pub fn capsule_collider(pos: [f32;2], radius: f32, height: f32) -> PhysObj {
+    PhysObj {
+        collider: Collider::Capsule { radius: radius, height: height },
+        ..box_collider(pos, [2.0*radius, height.max(2.0*radius)])
+    }
+}
+
+/// Right triangle filling the box `shape` from pos, rising to the right if `rising`
+pub fn ramp_collider(pos: [f32;2], shape: [f32;2], rising: bool) -> PhysObj {
+    let top = if rising { [shape[0], shape[1]] } else { [0.0, shape[1]] };
+    polygon_collider(pos, &[[0.0, 0.0], [shape[0], 0.0], top])
+}
+
+/// Convex polygon, `verts` are offsets from pos
+pub fn polygon_collider(pos: [f32;2], verts: &[[f32;2]]) -> PhysObj {
+    let polygon = ConvexPolygon::new(verts);
+    let mut shape = [0.0f32, 0.0f32];
+    for v in polygon.verts() {
+        shape = [shape[0].max(v[0]), shape[1].max(v[1])];
+    }
+    PhysObj {
+        collider: Collider::Polygon(polygon),
+        ..box_collider(pos, shape)
+    }
+}
+
 
 #[derive(Copy,Clone)]
 pub struct POSettings {
 	pub solid: bool,
 	pub gravity_strength: f32,
     pub terminal_velocity: f32,
+    pub mass: f32,
+    /// Layers this object is on
+    pub layer: u32,
+    /// Layers this object interacts with
+    pub mask: u32,
+    /// Never blocks, reports TriggerEnter/Stay/Exit to both objects instead
+    pub trigger: bool,
+    /// Sweep against solid statics so fast movement can't pass through them
+    pub continuous: bool,
+    /// Share of speed kept bouncing off a solid, 0.0 stops dead
+    pub restitution: f32,
+    /// Resistance to sliding along a solid, 0.0 is frictionless
+    pub friction: f32,
+    pub restitution_combine: CombineRule,
+    pub friction_combine: CombineRule,
+    /// Only block objects moving against this direction from its side, like a jump-through platform
+    pub one_way: Option<[f32;2]>,
+    /// Steepest slope (in degrees) this object can stand on without sliding
+    pub max_slope: f32,
+    /// Named behaviours run when something touches this, see Behaviours
+    pub behaviours: u32,
 	pub on_collision: fn(&PhysObj, &PhysObj) -> Vec<PhysEvent>,
 	pub on_each: fn(&PhysObj, &PhysObj) -> Vec<PhysEvent>
 	//...
@@ -97,63 +313,504 @@ impl std::default::Default for POSettings {
             solid: true,
             gravity_strength: 1.0,
             terminal_velocity: -10.0,
+            mass: 1.0,
+            layer: DEFAULT_LAYER,
+            mask: u32::MAX,
+            trigger: false,
+            continuous: false,
+            restitution: 0.0,
+            friction: 0.0,
+            restitution_combine: CombineRule::Average,
+            friction_combine: CombineRule::Average,
+            one_way: None,
+            max_slope: 45.0,
+            behaviours: 0,
             on_collision: |_,_| { Vec::new() },
             on_each: |_,_| { Vec::new() }
         }
     }
 }
 
+impl POSettings {
+    /// Copy with any settings present in `data` replaced, skipping anything invalid.
+    /// Layers and behaviours must be numbers here (see POMComponent::load_settings for names)
+    pub fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Self {
+        self.read(data, |_| None, |_| None, &mut Vec::new())
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let settings = self.read(data, |_| None, |_| None, &mut problems);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(settings)
+    }
+    /// `layer` and `behaviour` give the bits for names
+    fn read(&self, data: &serde_json::Map<String, serde_json::Value>, layer: impl Fn(&str) -> Option<u32>, behaviour: impl Fn(&str) -> Option<u32>, problems: &mut Vec<SceneProblem>) -> Self {
+        let mut settings = *self;
+
+        let rule = |key: &str, problems: &mut Vec<SceneProblem>| match data.get(key) {
+            Some(serde_json::Value::String(name)) => {
+                let rule = CombineRule::from_name(name);
+                if rule.is_none() {
+                    problems.push(SceneProblem::new(key, format!("unknown combine rule \"{}\"", name)));
+                }
+                rule
+            },
+            Some(_) => {
+                problems.push(SceneProblem::new(key, "expected a combine rule name"));
+                None
+            },
+            None => None
+        };
+
+        if let Some(solid) = bool_field(data, "solid", problems) { settings.solid = solid; }
+        if let Some(trigger) = bool_field(data, "trigger", problems) { settings.trigger = trigger; }
+        if let Some(continuous) = bool_field(data, "continuous", problems) { settings.continuous = continuous; }
+        if let Some(gravity_strength) = f32_field(data, "gravity_strength", problems) { settings.gravity_strength = gravity_strength; }
+        if let Some(terminal_velocity) = f32_field(data, "terminal_velocity", problems) { settings.terminal_velocity = terminal_velocity; }
+        if let Some(mass) = f32_field(data, "mass", problems) { settings.mass = mass; }
+        if let Some(restitution) = f32_field(data, "restitution", problems) { settings.restitution = restitution; }
+        if let Some(friction) = f32_field(data, "friction", problems) { settings.friction = friction; }
+        if let Some(layer) = named_bits(data, "layer", "layer", &layer, problems) { settings.layer = layer; }
+        if let Some(mask) = named_bits(data, "mask", "layer", &layer, problems) { settings.mask = mask; }
+        if let Some(behaviours) = named_bits(data, "behaviours", "behaviour", behaviour, problems) { settings.behaviours = behaviours; }
+        if let Some(restitution_combine) = rule("restitution_combine", problems) { settings.restitution_combine = restitution_combine; }
+        if let Some(friction_combine) = rule("friction_combine", problems) { settings.friction_combine = friction_combine; }
+        if let Some(max_slope) = f32_field(data, "max_slope", problems) { settings.max_slope = max_slope; }
+        match data.get("one_way") {
+            Some(serde_json::Value::Bool(false)) | Some(serde_json::Value::Null) => settings.one_way = None,
+            Some(serde_json::Value::Bool(true)) => settings.one_way = Some([0.0, 1.0]),
+            Some(direction) => match crate::try_vec2!(direction) {
+                Some(direction) => settings.one_way = normalize(direction),
+                None => problems.push(SceneProblem::new("one_way", "expected true, false or a direction [x, y]"))
+            },
+            None => ()
+        }
+
+        settings
+    }
+    /// Everything but the callbacks, in the format read by load.
+    /// Layers and behaviours are bits, see POMComponent::save_settings for names
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::json!({
+            "solid": self.solid,
+            "trigger": self.trigger,
+            "continuous": self.continuous,
+            "gravity_strength": self.gravity_strength,
+            "terminal_velocity": self.terminal_velocity,
+            "mass": self.mass,
+            "restitution": self.restitution,
+            "friction": self.friction,
+            "layer": self.layer,
+            "mask": self.mask,
+            "restitution_combine": self.restitution_combine.name(),
+            "friction_combine": self.friction_combine.name(),
+            "one_way": self.one_way,
+            "max_slope": self.max_slope,
+            "behaviours": self.behaviours
+        })
+    }
+}
+
+
+/// How the restitution or friction of two touching objects is combined,
+/// when the objects disagree the later rule in this list is used
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CombineRule {
+    Average,
+    Min,
+    Multiply,
+    Max
+}
+
+impl CombineRule {
+    pub fn combine(a: (f32, CombineRule), b: (f32, CombineRule)) -> f32 {
+        match a.1.max(b.1) {
+            CombineRule::Average => (a.0 + b.0) / 2.0,
+            CombineRule::Min => a.0.min(b.0),
+            CombineRule::Multiply => a.0 * b.0,
+            CombineRule::Max => a.0.max(b.0)
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "average" => Some(CombineRule::Average),
+            "min" => Some(CombineRule::Min),
+            "multiply" => Some(CombineRule::Multiply),
+            "max" => Some(CombineRule::Max),
+            _ => None
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            CombineRule::Average => "average",
+            CombineRule::Min => "min",
+            CombineRule::Multiply => "multiply",
+            CombineRule::Max => "max"
+        }
+    }
+}
+
+
 #[derive(Clone,Copy, Default)]
 pub enum PhysEvent {
+    /// Adds or replaces a static. The POM never moves it, and its delta isn't used to place it:
+    /// it's only the velocity things touching it see, per td like the delta of a dynamic
     StaticPO(Uuid, PhysObj),
+    /// Sent every tick in post_update. ModPos and ModVelocity from the POM are applied in
+    /// local_update, so the ones sent last tick are added to it before it's resolved
     DynamicPO(Uuid, PhysObj),
 
     RemoveStaticPO(Uuid),
+    MoveStaticPO(Uuid, [f32;2]),
+
+    /// Adds or updates a kinematic, moved by the POM at its delta each tick.
+    /// Updating keeps its path and where it's up to. The POM sends it ModPos as it moves
+    KinematicPO(Uuid, PhysObj),
+    /// Moves a kinematic along a path instead of at its delta
+    SetKinematicPath(Uuid, KinematicPath),
+    RemoveKinematicPO(Uuid),
+
+    /// Adds or replaces the joint with this uuid
+    AddJoint(Uuid, Joint),
+    RemoveJoint(Uuid),
+
+    /// Adds or replaces the force field with this uuid
+    AddField(Uuid, ForceField),
+    RemoveField(Uuid),
+    SetGravity([f32;2]),
+    /// Instant push on a dynamic, divided by its mass
+    ApplyImpulse(Uuid, [f32;2]),
+    /// Push on a dynamic for one tick, divided by its mass
+    ApplyForce(Uuid, [f32;2]),
 
     ModPos([f32;2]),
     ModVelocity([f32;2]),
 
 
     Collision(Uuid, PhysObj),
+    Blocked(Uuid, Side),
+    /// Sent to each dynamic every tick once it's resolved, even when nothing is touched
+    Contacts(ContactSummary),
+
+    TriggerEnter(Uuid),
+    TriggerStay(Uuid),
+    TriggerExit(Uuid),
 
     #[default]
     Null
 }
 
 
+/// Settings for a PhysObjManager, loaded from the "pom" component JSON
+#[derive(Clone)]
+pub struct POMSettings {
+    /// Size of each broadphase grid cell, 0.0 checks every static each tick
+    pub cell_size: f32,
+    /// Layer names, the layer at index i is bit 1 << i
+    pub layers: Vec<String>,
+    /// Velocity change per tick outside gravity fields
+    pub gravity: [f32;2]
+}
+
+impl std::default::Default for POMSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 4.0,
+            layers: vec!["default".to_string()],
+            gravity: [0.0, GRAVITY]
+        }
+    }
+}
+
+impl POMSettings {
+    /// Skips anything invalid, try_load reports it
+    pub fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Self {
+        self.read(data, &mut Vec::new())
+    }
+    pub fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let settings = self.read(data, &mut problems);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(settings)
+    }
+    fn read(&self, data: &serde_json::Map<String, serde_json::Value>, problems: &mut Vec<SceneProblem>) -> Self {
+        let mut settings = self.clone();
+        if let Some(cell_size) = f32_field(data, "cell_size", problems) {
+            if cell_size >= 0.0 {
+                settings.cell_size = cell_size;
+            } else {
+                problems.push(SceneProblem::new("cell_size", "must be 0 or above"));
+            }
+        }
+        if let Some(gravity) = vec2_field(data, "gravity", problems) {
+            settings.gravity = gravity;
+        }
+        match data.get("layers") {
+            Some(serde_json::Value::Array(layers)) => for (i, name) in layers.iter().enumerate() {
+                match name.as_str() {
+                    Some(name) if settings.layers.iter().any(|l| l == name) => (),
+                    Some(name) if settings.layers.len() < 32 => settings.layers.push(name.to_string()),
+                    Some(_) => problems.push(SceneProblem::new(format!("layers[{}]", i), "at most 32 layers")),
+                    None => problems.push(SceneProblem::new(format!("layers[{}]", i), "expected a string"))
+                }
+            },
+            Some(_) => problems.push(SceneProblem::new("layers", "expected an array of names")),
+            None => ()
+        }
+        settings
+    }
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::json!({
+            "cell_size": self.cell_size,
+            "layers": self.layers,
+            "gravity": self.gravity
+        })
+    }
+    /// Bit for a named layer
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        if name == "all" {
+            return Some(u32::MAX);
+        }
+        self.layers.iter().position(|l| l == name).map(|i| 1 << i)
+    }
+    /// Layer bits as names for layer_bits, with a number for any bits without one
+    pub fn save_layers(&self, bits: u32) -> serde_json::Value {
+        if bits == u32::MAX {
+            return "all".into();
+        }
+        let mut saved: Vec<serde_json::Value> = self.layers.iter().enumerate()
+            .filter(|(i, _)| bits & (1 << i) != 0)
+            .map(|(_, name)| name.clone().into())
+            .collect();
+        let unnamed = bits & !((1u64 << self.layers.len()) - 1) as u32;
+        if unnamed != 0 {
+            saved.push(unnamed.into());
+        }
+        saved.into()
+    }
+    /// Layer bits from JSON: a number, a layer name, or an array of either
+    pub fn layer_bits(&self, value: &serde_json::Value) -> Option<u32> {
+        match value {
+            serde_json::Value::Number(bits) => bits.as_u64().map(|bits| bits as u32),
+            serde_json::Value::String(name) => self.layer(name),
+            serde_json::Value::Array(values) => {
+                let mut bits = 0;
+                for v in values {
+                    bits |= self.layer_bits(v)?;
+                }
+                Some(bits)
+            },
+            _ => None
+        }
+    }
+}
+
+
+/// Colliders known to a PhysObjManager, shared with its POMComponent for queries
+struct World {
+    statics: HashMap<Uuid, PhysObj>,
+    broadphase: Option<SpatialHash>,
+    /// Dynamics from the last tick, after resolution
+    dynamics: Vec<(Uuid, PhysObj)>,
+    kinematics: BTreeMap<Uuid, Kinematic>,
+    /// Pairs touching a trigger last tick, smallest uuid first
+    triggered: HashSet<(Uuid, Uuid)>,
+    /// Solved in the order they were added
+    joints: Vec<(Uuid, Joint)>,
+    gravity: [f32;2],
+    /// Later fields override the gravity of earlier ones
+    fields: Vec<(Uuid, ForceField)>,
+    /// Points and normals where dynamics were blocked last tick
+    contacts: Vec<([f32;2],[f32;2])>,
+    /// ModPos and ModVelocity totals sent to each object last tick. Elements apply them
+    /// in local_update, after sending the DynamicPO this tick resolves, so they're added to it
+    in_flight: BTreeMap<Uuid, ([f32;2],[f32;2])>
+}
+
+impl World {
+    fn new(settings: &POMSettings) -> Self {
+        Self {
+            statics: HashMap::new(),
+            broadphase: if settings.cell_size > 0.0 { Some(SpatialHash::new(settings.cell_size)) } else { None },
+            dynamics: Vec::new(),
+            kinematics: BTreeMap::new(),
+            triggered: HashSet::new(),
+            joints: Vec::new(),
+            gravity: settings.gravity,
+            fields: Vec::new(),
+            contacts: Vec::new(),
+            in_flight: BTreeMap::new()
+        }
+    }
+    fn add_static(&mut self, uuid: Uuid, po: PhysObj) {
+        self.statics.insert(uuid, po);
+        let bounds = self.static_po(&uuid).bounds();
+        if let Some(broadphase) = &mut self.broadphase {
+            broadphase.insert(uuid, bounds);
+        }
+    }
+    fn move_static(&mut self, uuid: Uuid, pos: [f32;2]) {
+        if let Some(po) = self.statics.get_mut(&uuid) {
+            po.pos = pos;
+            let bounds = self.static_po(&uuid).bounds();
+            if let Some(broadphase) = &mut self.broadphase {
+                broadphase.insert(uuid, bounds);
+            }
+        }
+    }
+    /// A static where it is, without its delta, which is a velocity and doesn't move it
+    fn static_po(&self, uuid: &Uuid) -> PhysObj {
+        PhysObj { delta: [0.0, 0.0], ..self.statics[uuid] }
+    }
+    fn remove_static(&mut self, uuid: &Uuid) {
+        if let Some(broadphase) = &mut self.broadphase {
+            broadphase.remove(uuid);
+        }
+        self.statics.remove(uuid);
+    }
+    fn add_joint(&mut self, uuid: Uuid, joint: Joint) {
+        match self.joints.iter_mut().find(|(j_uuid, _)| *j_uuid == uuid) {
+            Some(existing) => existing.1 = joint,
+            None => self.joints.push((uuid, joint))
+        }
+    }
+    fn remove_joint(&mut self, uuid: &Uuid) {
+        self.joints.retain(|(j_uuid, _)| j_uuid != uuid);
+    }
+    fn add_field(&mut self, uuid: Uuid, field: ForceField) {
+        match self.fields.iter_mut().find(|(f_uuid, _)| *f_uuid == uuid) {
+            Some(existing) => existing.1 = field,
+            None => self.fields.push((uuid, field))
+        }
+    }
+    fn remove_field(&mut self, uuid: &Uuid) {
+        self.fields.retain(|(f_uuid, _)| f_uuid != uuid);
+    }
+    /// Gravity at `point` for objects on `layer`, the last gravity field containing it wins
+    fn gravity_at(&self, point: [f32;2], layer: u32) -> [f32;2] {
+        self.fields.iter()
+            .filter(|(_, field)| field.mask & layer != 0 && field.contains(point))
+            .filter_map(|(_, field)| field.gravity(point))
+            .next_back()
+            .unwrap_or(self.gravity)
+    }
+    /// Statics that could be touching `bounds`, sorted
+    fn nearby_statics(&self, bounds: ([f32;2],[f32;2])) -> Vec<Uuid> {
+        match &self.broadphase {
+            Some(broadphase) => broadphase.query(bounds),
+            None => {
+                let mut all: Vec<Uuid> = self.statics.keys().copied().collect();
+                all.sort();
+                all
+            }
+        }
+    }
+    /// FNV-1a of the whole world: every object, joint and field, gravity,
+    /// trigger pairs and corrections in flight
+    fn hash_state(&self) -> u64 {
+        let mut hash = Fnv(FNV_OFFSET);
+        let mut statics: Vec<(&Uuid, &PhysObj)> = self.statics.iter().collect();
+        statics.sort_by_key(|(uuid, _)| **uuid);
+        for (uuid, po) in statics {
+            hash.write(uuid.as_bytes());
+            hash.write_po(po);
+        }
+        for (uuid, po) in &self.dynamics {
+            hash.write(uuid.as_bytes());
+            hash.write_po(po);
+        }
+        for (uuid, kinematic) in &self.kinematics {
+            hash.write(uuid.as_bytes());
+            kinematic.write_hash(&mut hash);
+        }
+        for (uuid, joint) in &self.joints {
+            hash.write(uuid.as_bytes());
+            hash.write_joint(joint);
+        }
+        hash.write_vec2(self.gravity);
+        for (uuid, field) in &self.fields {
+            hash.write(uuid.as_bytes());
+            hash.write_field(field);
+        }
+        let mut triggered: Vec<&(Uuid, Uuid)> = self.triggered.iter().collect();
+        triggered.sort();
+        for (a, b) in triggered {
+            hash.write(a.as_bytes());
+            hash.write(b.as_bytes());
+        }
+        for (uuid, (push, change)) in &self.in_flight {
+            hash.write(uuid.as_bytes());
+            hash.write_vec2(*push);
+            hash.write_vec2(*change);
+        }
+        hash.0
+    }
+}
+
+
 #[derive(Clone)]
 pub struct PhysObjManager {
-    statics: Vec<(Uuid, PhysObj)>,
+    settings: POMSettings,
+    behaviours: Rc<RefCell<Behaviours>>,
+    world: Rc<RefCell<World>>,
     receiver: Rc<Receiver<PhysEvent>>,
-    priv_sender: Sender<PhysEvent>,
 
     router: Router<PhysEvent>,
     
-    send_queue: Vec<RoutedEvent<PhysEvent>>,
+    send_queue: Rc<RefCell<Vec<RoutedEvent<PhysEvent>>>>,
 
     component: POMComponent
 }
 
 impl PhysObjManager {
     pub fn new() -> Self {
+        Self::with_settings(POMSettings::default())
+    }
+    pub fn with_settings(settings: POMSettings) -> Self {
         let ps = Sender::new();
         let router = Router::new();
+        let world = Rc::new(RefCell::new(World::new(&settings)));
+        let behaviours = Rc::new(RefCell::new(Behaviours::default()));
+        let receiver = ps.new_receiver();
+        let send_queue = Rc::new(RefCell::new(Vec::new()));
 
         Self {
-            statics: Vec::new(),
-            receiver: ps.new_receiver(),
-            priv_sender: ps.clone(),
+            behaviours: behaviours.clone(),
+            world: world.clone(),
+            receiver: receiver.clone(),
 
             router: router.clone(),
 
-            send_queue: Vec::new(),
+            send_queue: send_queue.clone(),
 
             component: POMComponent {
                 priv_sender: ps,
-                router: router
-            }
+                router: router,
+                world: world,
+                receiver: receiver,
+                send_queue: send_queue,
+                behaviours: behaviours,
+                settings: Rc::new(settings.clone())
+            },
+            settings: settings
         }
     }
+    pub fn snapshot(&self) -> PhysSnapshot {
+        self.component.snapshot()
+    }
+    pub fn restore(&self, snapshot: &PhysSnapshot) {
+        self.component.restore(snapshot)
+    }
+    /// Registers a behaviour for every scene this is loaded into
+    pub fn with_behaviour(self, name: &str, behaviour: impl Fn(&Contact) -> Vec<RoutedEvent<PhysEvent>> + 'static) -> Self {
+        self.behaviours.borrow_mut().register(name, behaviour);
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -161,6 +818,13 @@ pub struct POMComponent {
     pub priv_sender: Sender<PhysEvent>,
 
     pub router: Router<PhysEvent>,
+
+    world: Rc<RefCell<World>>,
+    /// Shared with the PhysObjManager, for snapshots
+    receiver: Rc<Receiver<PhysEvent>>,
+    send_queue: Rc<RefCell<Vec<RoutedEvent<PhysEvent>>>>,
+    behaviours: Rc<RefCell<Behaviours>>,
+    settings: Rc<POMSettings>
 }
 impl POMComponent {
     pub fn new_receiver(&self) -> (Uuid, Rc<Receiver<PhysEvent>>) {
@@ -172,6 +836,74 @@ impl POMComponent {
     pub fn new_receiver_uuid(&self, uuid: Uuid) -> (Uuid, Rc<Receiver<PhysEvent>>) {
         self.router.new_receiver_uuid(uuid)
     }
+    /// Bit for a layer named in the "pom" component JSON
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        self.settings.layer(name)
+    }
+    /// Layer bits from JSON: a number, a layer name, or an array of either
+    pub fn layer_bits(&self, value: &serde_json::Value) -> Option<u32> {
+        self.settings.layer_bits(value)
+    }
+    /// Joins two objects, returns the uuid to remove the joint with
+    pub fn add_joint(&self, joint: Joint) -> Uuid {
+        let uuid = crate::ids::new_uuid();
+        self.priv_sender.send(PhysEvent::AddJoint(uuid, joint));
+        uuid
+    }
+    pub fn remove_joint(&self, uuid: Uuid) {
+        self.priv_sender.send(PhysEvent::RemoveJoint(uuid));
+    }
+    /// Adds a force field, returns the uuid to remove it with
+    pub fn add_field(&self, field: ForceField) -> Uuid {
+        let uuid = crate::ids::new_uuid();
+        self.priv_sender.send(PhysEvent::AddField(uuid, field));
+        uuid
+    }
+    pub fn remove_field(&self, uuid: Uuid) {
+        self.priv_sender.send(PhysEvent::RemoveField(uuid));
+    }
+    /// World gravity, outside any gravity fields
+    pub fn gravity(&self) -> [f32;2] {
+        self.world.borrow().gravity
+    }
+    /// Hash of the physics state after the last tick, equal across runs given the same
+    /// inputs and ids::seed. Hashes the whole world on every call, so it costs nothing unless used
+    pub fn state_hash(&self) -> u64 {
+        self.world.borrow().hash_state()
+    }
+    /// Every joint, as of the last tick
+    pub fn joints(&self) -> Vec<(Uuid, Joint)> {
+        self.world.borrow().joints.clone()
+    }
+    /// Registers a behaviour for this scene only, so it can capture element state
+    pub fn register_behaviour(&self, name: &str, behaviour: impl Fn(&Contact) -> Vec<RoutedEvent<PhysEvent>> + 'static) -> Option<u32> {
+        self.behaviours.borrow_mut().register(name, behaviour)
+    }
+    /// Bit for a named behaviour
+    pub fn behaviour(&self, name: &str) -> Option<u32> {
+        self.behaviours.borrow().bit(name)
+    }
+    pub fn behaviour_names(&self, bits: u32) -> Vec<String> {
+        self.behaviours.borrow().names(bits)
+    }
+    /// POSettings::load, also accepting layer names for "layer" and "mask",
+    /// and behaviour names for "behaviours". Unknown names are problems
+    pub fn load_settings(&self, settings: &POSettings, data: &serde_json::Map<String, serde_json::Value>) -> Result<POSettings, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let settings = settings.read(data, |name| self.layer(name), |name| self.behaviours.borrow().bit(name), &mut problems);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(settings)
+    }
+    /// POSettings::save with layer and behaviour names, in the format read by load_settings
+    pub fn save_settings(&self, settings: &POSettings) -> serde_json::Value {
+        let mut data = settings.save();
+        data["layer"] = self.settings.save_layers(settings.layer);
+        data["mask"] = self.settings.save_layers(settings.mask);
+        data["behaviours"] = self.behaviours.borrow().save_bits(settings.behaviours);
+        data
+    }
 }
 
 impl Component for PhysObjManager {
@@ -184,8 +916,101 @@ impl Component for PhysObjManager {
     fn build_element(&self) -> Element {
         Element::Generic(Box::new(self.clone()))
     }
+    /// Settings, joints and fields. Objects are left to the elements that add them
+    fn save(&self) -> serde_json::Value {
+        let world = self.world.borrow();
+        let with_uuid = |uuid: &Uuid, mut data: serde_json::Value| {
+            data["uuid"] = uuid.to_string().into();
+            data
+        };
+
+        let mut data = POMSettings { gravity: world.gravity, ..self.settings.clone() }.save();
+        data["joints"] = world.joints.iter().map(|(uuid, joint)| with_uuid(uuid, joint.save())).collect();
+        data["fields"] = world.fields.iter().map(|(uuid, field)| with_uuid(uuid, field.save())).collect();
+        data
+    }
+    /// Skips anything invalid, try_load reports it
     fn load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Box<dyn Component> {
-        Box::new(self.clone())
+        Box::new(self.read(data, &mut Vec::new()))
+    }
+    fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Box<dyn Component>, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let pom = self.read(data, &mut problems);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Box::new(pom))
+    }
+}
+
+impl PhysObjManager {
+    /// A new manager with this one's behaviours, loaded from `data`
+    fn read(&self, data: &serde_json::Map<String, serde_json::Value>, problems: &mut Vec<SceneProblem>) -> Self {
+        let mut pom = PhysObjManager::with_settings(self.settings.read(data, problems));
+        pom.behaviours = Rc::new(RefCell::new(self.behaviours.borrow().clone()));
+        pom.component.behaviours = pom.behaviours.clone();
+
+        // each joint may have a "uuid", so it can be removed later
+        for (path, data) in Self::objects(data, "joints", problems) {
+            let mut found = Vec::new();
+            let uuid = uuid_field(data, "uuid", &mut found);
+            match Joint::try_load(data) {
+                Ok(joint) if found.is_empty() => pom.world.borrow_mut().add_joint(uuid.unwrap_or_else(crate::ids::new_uuid), joint),
+                Ok(_) => (),
+                Err(errors) => found.extend(errors)
+            }
+            problems.extend(found.into_iter().map(|p| p.within(&path)));
+        }
+
+        // "mask" can use layer names, and each field may have a "uuid" like joints
+        for (path, data) in Self::objects(data, "fields", problems) {
+            let mut found = Vec::new();
+            let uuid = uuid_field(data, "uuid", &mut found);
+            let mut data = data.clone();
+            let mask = data.remove("mask").map(|mask| pom.settings.layer_bits(&mask));
+            if let Some(None) = mask {
+                found.push(SceneProblem::new("mask", "expected layer names or bits"));
+            }
+            match ForceField::try_load(&data) {
+                Ok(mut field) if found.is_empty() => {
+                    field.mask = mask.flatten().unwrap_or(u32::MAX);
+                    pom.world.borrow_mut().add_field(uuid.unwrap_or_else(crate::ids::new_uuid), field);
+                },
+                Ok(_) => (),
+                Err(errors) => found.extend(errors)
+            }
+            problems.extend(found.into_iter().map(|p| p.within(&path)));
+        }
+
+        // a saved snapshot replaces everything, joints included
+        if let Some(snapshot) = object_field(data, "snapshot", problems) {
+            match PhysSnapshot::try_load(snapshot) {
+                Ok(snapshot) => pom.restore(&snapshot),
+                Err(errors) => problems.extend(errors.into_iter().map(|p| p.within("snapshot")))
+            }
+        }
+
+        pom
+    }
+    /// Objects in the array at `key` with their paths, a problem for anything else
+    fn objects<'a>(data: &'a serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Vec<(String, &'a serde_json::Map<String, serde_json::Value>)> {
+        let values = match data.get(key) {
+            Some(serde_json::Value::Array(values)) => values,
+            Some(_) => {
+                problems.push(SceneProblem::new(key, "expected an array"));
+                return Vec::new();
+            },
+            None => return Vec::new()
+        };
+        let mut objects = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            let path = format!("{}[{}]", key, i);
+            match value.as_object() {
+                Some(object) => objects.push((path, object)),
+                None => problems.push(SceneProblem::new(path, "expected an object"))
+            }
+        }
+        objects
     }
 }
 
@@ -194,8 +1019,13 @@ impl ElementBase for PhysObjManager {
     fn init(&mut self, uuid: uuid::Uuid, components: &crate::component::ComponentManager) {
     }
     fn local_update(&mut self, td: f32) {
+        let world = self.world.clone();
+        let mut world = world.borrow_mut();
         
+        let mut queue = Vec::new();
         let mut dynamics = Vec::new();
+        // impulses and forces for each dynamic, before dividing by mass
+        let mut applied: HashMap<Uuid, [f32;2]> = HashMap::new();
         for e in self.receiver.poll() {
             
             match e {
@@ -203,26 +1033,162 @@ impl ElementBase for PhysObjManager {
                     dynamics.push((uuid,po));
                 },
                 PhysEvent::StaticPO(uuid, po) => {
-                    self.statics.push((uuid,po));
+                    // nil statics can't be told apart, so they can't be replaced or removed
+                    let uuid = if uuid.is_nil() { crate::ids::new_uuid() } else { uuid };
+                    world.add_static(uuid, po);
+                },
+                PhysEvent::MoveStaticPO(uuid, pos) => {
+                    world.move_static(uuid, pos);
+                },
+                PhysEvent::RemoveStaticPO(uuid) => {
+                    world.remove_static(&uuid);
+                },
+                PhysEvent::KinematicPO(uuid, po) => match world.kinematics.get_mut(&uuid) {
+                    Some(kinematic) => kinematic.set_po(po),
+                    None => {
+                        world.kinematics.insert(uuid, Kinematic::new(po));
+                    }
+                },
+                PhysEvent::SetKinematicPath(uuid, path) => {
+                    if let Some(kinematic) = world.kinematics.get_mut(&uuid) {
+                        kinematic.set_path(path);
+                    }
+                },
+                PhysEvent::RemoveKinematicPO(uuid) => {
+                    world.kinematics.remove(&uuid);
+                },
+                PhysEvent::AddJoint(uuid, joint) => {
+                    world.add_joint(uuid, joint);
+                },
+                PhysEvent::RemoveJoint(uuid) => {
+                    world.remove_joint(&uuid);
+                },
+                PhysEvent::AddField(uuid, field) => {
+                    world.add_field(uuid, field);
+                },
+                PhysEvent::RemoveField(uuid) => {
+                    world.remove_field(&uuid);
+                },
+                PhysEvent::SetGravity(gravity) => {
+                    world.gravity = gravity;
+                },
+                PhysEvent::ApplyImpulse(uuid, impulse) => {
+                    let total = applied.entry(uuid).or_insert([0.0, 0.0]);
+                    *total = add(*total, impulse);
+                },
+                PhysEvent::ApplyForce(uuid, force) => {
+                    let total = applied.entry(uuid).or_insert([0.0, 0.0]);
+                    *total = add(*total, scale(force, td));
                 },
                 _ => ()
             }
         }
 
-        for (uuid, d) in dynamics {
-            self.send_queue.append(&mut
-                self.individual((&uuid, &d), td)
+        for kinematic in world.kinematics.values_mut() {
+            kinematic.start_tick(td);
+        }
+
+        for (uuid, po) in dynamics.iter_mut() {
+            if let Some((push, change)) = world.in_flight.get(uuid) {
+                po.pos = add(po.pos, *push);
+                po.delta = add(po.delta, *change);
+            }
+        }
+        let mut bodies: Vec<Body> = dynamics.into_iter().map(|(uuid, d)| Body::new(uuid, d, td)).collect();
+
+        for body in bodies.iter_mut() {
+            queue.append(&mut
+                self.individual((&body.uuid, &body.start), &world, applied.get(&body.uuid).copied().unwrap_or([0.0, 0.0]), td)
             );
-            for (s_uuid, s) in &self.statics {
-                self.send_queue.append(&mut
-                    self.interaction((&uuid, &d), (s_uuid, s), td)
+            body.ride(&world.kinematics);
+        }
+
+        // before collisions, so a joint can't pull a body back into a wall
+        {
+            let world = &mut *world;
+            solve_joints(&mut world.joints, &mut bodies, &world.statics, &world.kinematics, td);
+        }
+
+        for body in bodies.iter_mut() {
+            if body.po.settings.continuous {
+                queue.append(&mut
+                    self.sweep(body, &world)
+                );
+            }
+            // deepest first, so being pushed out of one block doesn't catch on the edge of the next
+            let mut nearby = world.nearby_statics(body.po.bounds());
+            nearby.sort_by(|a, b| overlap_area(&body.po, &world.static_po(b)).total_cmp(&overlap_area(&body.po, &world.static_po(a))));
+            for s_uuid in nearby {
+                queue.append(&mut
+                    self.interaction(body, (&s_uuid, &world.static_po(&s_uuid)), world.statics[&s_uuid].delta)
                 );
                 
             }
+            for (k_uuid, kinematic) in &world.kinematics {
+                queue.append(&mut
+                    self.interaction(body, (k_uuid, &kinematic.po), kinematic.tick_velocity(td))
+                );
+            }
         }
+
+        for i in 0..bodies.len() {
+            let (head, tail) = bodies.split_at_mut(i+1);
+            let this = &mut head[i];
+            for other in tail.iter_mut() {
+                queue.append(&mut
+                    self.dynamic_interaction(this, other)
+                );
+            }
+        }
+
+        for body in &bodies {
+            queue.append(&mut body.resolution());
+            let up = normalize(world.gravity_at(body.start.core().centre(), body.po.settings.layer)).map_or([0.0, 1.0], |down| scale(down, -1.0));
+            queue.push(RoutedEvent(Some(body.uuid), PhysEvent::Contacts(body.summary(up))));
+        }
+        for (k_uuid, kinematic) in world.kinematics.iter_mut() {
+            let step = kinematic.finish_tick();
+            if step != [0.0, 0.0] {
+                queue.push(RoutedEvent(Some(*k_uuid), PhysEvent::ModPos(step)));
+            }
+        }
+
+        let triggered: HashSet<(Uuid, Uuid)> = bodies.iter()
+            .flat_map(|body| body.triggers.iter().map(|other| (body.uuid.min(*other), body.uuid.max(*other))))
+            .collect();
+        queue.append(&mut Self::trigger_events(&world.triggered, &triggered));
+
+        world.dynamics = bodies.iter().map(|body| (body.uuid, body.po)).collect();
+        world.triggered = triggered;
+        world.contacts = bodies.iter().flat_map(|body| body.touches.iter().map(|touch| (touch.point, touch.normal))).collect();
+
+        // after the world is released, so behaviours can query it
+        drop(world);
+        for contact in bodies.iter().flat_map(|body| body.contacts.iter()) {
+            let behaviours = self.behaviours.borrow().matching(contact.po.settings.behaviours);
+            for behaviour in behaviours {
+                queue.append(&mut behaviour(contact));
+            }
+        }
+
+        let mut in_flight: BTreeMap<Uuid, ([f32;2],[f32;2])> = BTreeMap::new();
+        for RoutedEvent(uuid, e) in &queue {
+            let total = match uuid {
+                Some(uuid) => in_flight.entry(*uuid).or_insert(([0.0, 0.0], [0.0, 0.0])),
+                None => continue
+            };
+            match e {
+                PhysEvent::ModPos(push) => total.0 = add(total.0, *push),
+                PhysEvent::ModVelocity(change) => total.1 = add(total.1, *change),
+                _ => ()
+            }
+        }
+        self.world.borrow_mut().in_flight = in_flight;
+        self.send_queue.borrow_mut().append(&mut queue);
     }
     fn post_update(&mut self) {
-        for e in self.send_queue.drain(..) {
+        let queue: Vec<RoutedEvent<PhysEvent>> = self.send_queue.borrow_mut().drain(..).collect();
+        for e in queue {
             self.router.send(e);
         }
     }
@@ -232,41 +1198,167 @@ impl ElementBase for PhysObjManager {
 
 
 impl PhysObjManager {
-    fn individual(&self, this: (&Uuid, &PhysObj), td: f32) -> Vec<RoutedEvent<PhysEvent>> {
+    /// Enter, stay and exit events for both objects of each trigger pair
+    fn trigger_events(previous: &HashSet<(Uuid, Uuid)>, current: &HashSet<(Uuid, Uuid)>) -> Vec<RoutedEvent<PhysEvent>> {
+        let mut queue = Vec::new();
+
+        let mut pairs: Vec<&(Uuid, Uuid)> = previous.union(current).collect();
+        pairs.sort();
+        for pair in pairs {
+            let event: fn(Uuid) -> PhysEvent = match (previous.contains(pair), current.contains(pair)) {
+                (false, true) => PhysEvent::TriggerEnter,
+                (true, true) => PhysEvent::TriggerStay,
+                _ => PhysEvent::TriggerExit
+            };
+            let (a, b) = *pair;
+            queue.push(RoutedEvent(Some(a), event(b)));
+            queue.push(RoutedEvent(Some(b), event(a)));
+        }
+
+        queue
+    }
+    /// Gravity, fields, impulses and forces (`applied`) on one dynamic
+    fn individual(&self, this: (&Uuid, &PhysObj), world: &World, applied: [f32;2], td: f32) -> Vec<RoutedEvent<PhysEvent>> {
         let mut queue = Vec::new();
 
         let (uuid, this) = this;
+        let centre = this.core().centre();
+        // massless objects take forces as a velocity change
+        let mass = if this.settings.mass > 0.0 { this.settings.mass } else { 1.0 };
+
+        let gravity = world.gravity_at(centre, this.settings.layer);
+        let mut change = scale(applied, 1.0/mass);
+        for (_, field) in &world.fields {
+            if field.mask & this.settings.layer == 0 || !field.contains(centre) {
+                continue;
+            }
+            match field.kind {
+                FieldKind::Wind(force) => change = add(change, scale(force, td/mass)),
+                FieldKind::Drag(amount) => change = sub(change, scale(this.delta, (amount*td).clamp(0.0, 1.0))),
+                _ => ()
+            }
+        }
 
+        // terminal velocity is along the direction of gravity
+        let falling = normalize(gravity).map_or(0.0, |down| dot(this.delta, down));
+        if this.settings.gravity_strength != 0.0 && -falling > this.settings.terminal_velocity {
+            change = add(change, scale(gravity, this.settings.gravity_strength*td));
+        }
 
-        if this.settings.gravity_strength != 0.0 && this.delta[1] > this.settings.terminal_velocity  {
-            queue.push(RoutedEvent(Some(*uuid), PhysEvent::ModVelocity([0.0, GRAVITY*this.settings.gravity_strength*td])));
+        if change != [0.0, 0.0] {
+            queue.push(RoutedEvent(Some(*uuid), PhysEvent::ModVelocity(change)));
         }
 
+        queue
+    }
+    /// Stops a body at the first solid static it would pass into this tick
+    fn sweep(&self, this: &mut Body, world: &World) -> Vec<RoutedEvent<PhysEvent>> {
+        let mut queue = Vec::new();
 
+        let translation = this.po.delta;
+        let from = PhysObj { delta: [0.0, 0.0], ..this.po };
+        let (start_min, start_max) = from.bounds();
+        let (end_min, end_max) = this.po.bounds();
+        let bounds = (
+            [start_min[0].min(end_min[0]), start_min[1].min(end_min[1])],
+            [start_max[0].max(end_max[0]), start_max[1].max(end_max[1])]
+        );
+
+        let mut first: Option<(f32, [f32;2], Uuid)> = None;
+        for s_uuid in world.nearby_statics(bounds) {
+            let other = &world.static_po(&s_uuid);
+            if !other.settings.solid || other.settings.trigger || !this.po.interacts_with(other) {
+                continue;
+            }
+            // hits at 0.0 are already touching, which the discrete pass handles
+            if let Some((t, _, normal)) = time_of_impact(&from, translation, other) {
+                let passes_one_way = other.settings.one_way.is_some_and(|up| dot(normal, up) <= 0.0);
+                if t > 0.0 && t < 1.0 && dot(translation, normal) < 0.0 && !passes_one_way && first.is_none_or(|(best, _, _)| t < best) {
+                    first = Some((t, normal, s_uuid));
+                }
+            }
+        }
+
+        if let Some((t, normal, s_uuid)) = first {
+            let other = &world.static_po(&s_uuid);
+            let other_velocity = world.statics[&s_uuid].delta;
+
+            // the object reaches pos + delta before this is applied, so move it back to the hit
+            this.push(scale(translation, t - 1.0));
+            this.contact(s_uuid, other, other_velocity);
+            this.touch(s_uuid, other, normal, other_velocity);
+            let (dv, _) = contact_response(normal, this.current_velocity(), other_velocity, 1.0, &this.po.settings, &other.settings);
+            this.velocity = add(this.velocity, dv);
+
+            this.swept = Some(s_uuid);
+            queue.push(RoutedEvent(Some(this.uuid), PhysEvent::Collision(s_uuid, *other)));
+            for e in (this.po.settings.on_collision)(&this.po,other) {
+                queue.push(RoutedEvent(Some(this.uuid), e));
+            }
+            if let Some(side) = Side::from_push(normal) {
+                queue.push(RoutedEvent(Some(this.uuid), PhysEvent::Blocked(s_uuid, side)));
+            }
+        }
 
         queue
     }
-    fn interaction(&self, this: (&Uuid, &PhysObj), other: (&Uuid, &PhysObj), td: f32) -> Vec<RoutedEvent<PhysEvent>> {
+    /// `other_velocity` is per td, like the delta of a dynamic
+    fn interaction(&self, this: &mut Body, other: (&Uuid, &PhysObj), other_velocity: [f32;2]) -> Vec<RoutedEvent<PhysEvent>> {
         let mut queue = Vec::new();
 
-        let (this_uuid, this) = this;
+        let this_uuid = this.uuid;
         let (other_uuid, other) = other;
 
+        if !this.po.interacts_with(other) {
+            return queue;
+        }
+
+        for e in (this.po.settings.on_each)(&this.po,other) {
+            queue.push(RoutedEvent(Some(this_uuid), e))
+        }
 
-        for e in (this.settings.on_each)(this,other) {
-            queue.push(RoutedEvent(Some(*this_uuid), e))
+        // already reported by the sweep
+        if this.swept == Some(*other_uuid) {
+            return queue;
         }
 
 
-        if this.intersects(other) {
-            queue.push(RoutedEvent(Some(*this_uuid), PhysEvent::Collision(*other_uuid, *other)));
+        if this.po.intersects(other) {
+            queue.push(RoutedEvent(Some(this_uuid), PhysEvent::Collision(*other_uuid, *other)));
+            this.contact(*other_uuid, other, other_velocity);
 
 
-            for e in (this.settings.on_collision)(this,other) {
-                queue.push(RoutedEvent(Some(*this_uuid), e));
+            for e in (this.po.settings.on_collision)(&this.po,other) {
+                queue.push(RoutedEvent(Some(this_uuid), e));
             }     
 
-            if this.settings.solid && other.settings.solid {
+            if this.po.settings.trigger || other.settings.trigger {
+                this.triggers.push(*other_uuid);
+            } else if this.po.settings.solid && other.settings.solid && this.blocked_by_one_way(other) {
+                let mut push = this.po.exclusive_delta(other);
+                let mut normal = normalize(push);
+                let surface = normal;
+
+                // walkable slopes push straight up, so objects standing on them don't slide down
+                if let Some(n) = normal {
+                    if n[1] > 0.0 && n[1] >= this.po.settings.max_slope.to_radians().cos() {
+                        push = [0.0, length(push) / n[1]];
+                        normal = Some([0.0, 1.0]);
+                    }
+                }
+                this.push(push);
+
+                if let (Some(normal), Some(surface)) = (normal, surface) {
+                    this.touch(*other_uuid, other, surface, other_velocity);
+                    // riders already move with what carries them
+                    let other_velocity = if this.carried == Some(*other_uuid) { [0.0, 0.0] } else { other_velocity };
+                    let (dv, _) = contact_response(normal, this.current_velocity(), other_velocity, 1.0, &this.po.settings, &other.settings);
+                    this.velocity = add(this.velocity, dv);
+                }
+
+                if let Some(side) = Side::from_push(push) {
+                    queue.push(RoutedEvent(Some(this_uuid), PhysEvent::Blocked(*other_uuid, side)));
+                }
             }
         }
 
@@ -274,8 +1366,372 @@ impl PhysObjManager {
 
 
 
+        queue
+    }
+    fn dynamic_interaction(&self, this: &mut Body, other: &mut Body) -> Vec<RoutedEvent<PhysEvent>> {
+        let mut queue = Vec::new();
+
+        if !this.po.interacts_with(&other.po) {
+            return queue;
+        }
+
+        for e in (this.po.settings.on_each)(&this.po,&other.po) {
+            queue.push(RoutedEvent(Some(this.uuid), e))
+        }
+        for e in (other.po.settings.on_each)(&other.po,&this.po) {
+            queue.push(RoutedEvent(Some(other.uuid), e))
+        }
+
+
+        if this.po.intersects(&other.po) {
+            queue.push(RoutedEvent(Some(this.uuid), PhysEvent::Collision(other.uuid, other.po)));
+            queue.push(RoutedEvent(Some(other.uuid), PhysEvent::Collision(this.uuid, this.po)));
+            this.contact(other.uuid, &other.po, other.current_velocity());
+
+
+            for e in (this.po.settings.on_collision)(&this.po,&other.po) {
+                queue.push(RoutedEvent(Some(this.uuid), e));
+            }
+            for e in (other.po.settings.on_collision)(&other.po,&this.po) {
+                queue.push(RoutedEvent(Some(other.uuid), e));
+            }
+
+            if this.po.settings.trigger || other.po.settings.trigger {
+                this.triggers.push(other.uuid);
+            } else if this.po.settings.solid && other.po.settings.solid {
+                let push = this.po.exclusive_delta(&other.po);
+
+                // heavier objects get pushed less
+                let this_mass = this.po.settings.mass.max(0.0);
+                let other_mass = other.po.settings.mass.max(0.0);
+                let total_mass = this_mass + other_mass;
+                let this_share = if total_mass > 0.0 { other_mass / total_mass } else { 0.5 };
+
+                this.push([push[0]*this_share, push[1]*this_share]);
+                other.push([-push[0]*(1.0-this_share), -push[1]*(1.0-this_share)]);
+
+                if let Some(normal) = normalize(push) {
+                    this.touch(other.uuid, &other.po, normal, other.current_velocity());
+                    other.touch(this.uuid, &this.po, scale(normal, -1.0), this.current_velocity());
+                    let (this_dv, other_dv) = contact_response(normal, this.current_velocity(), other.current_velocity(), this_share, &this.po.settings, &other.po.settings);
+                    this.velocity = add(this.velocity, this_dv);
+                    other.velocity = add(other.velocity, other_dv);
+                }
+
+                if let Some(side) = Side::from_push(push) {
+                    queue.push(RoutedEvent(Some(this.uuid), PhysEvent::Blocked(other.uuid, side)));
+                }
+                if let Some(side) = Side::from_push([-push[0], -push[1]]) {
+                    queue.push(RoutedEvent(Some(other.uuid), PhysEvent::Blocked(this.uuid, side)));
+                }
+            }
+        }
+
+
+        queue
+    }
+}
+
+
+/// Velocity changes for objects a and b touching along `normal` (pointing towards a).
+/// `share` is how much of the change a takes, 1.0 when b can't move
+fn contact_response(normal: [f32;2], a_velocity: [f32;2], b_velocity: [f32;2], share: f32, a: &POSettings, b: &POSettings) -> ([f32;2], [f32;2]) {
+    let relative = sub(a_velocity, b_velocity);
+    let approach = dot(relative, normal);
+    if approach >= 0.0 {
+        return ([0.0, 0.0], [0.0, 0.0]);
+    }
+
+    // small bounces are dropped so resting objects settle
+    let restitution = if -approach > RESTITUTION_THRESHOLD {
+        CombineRule::combine((a.restitution, a.restitution_combine), (b.restitution, b.restitution_combine))
+    } else {
+        0.0
+    };
+    let normal_change = -(1.0 + restitution) * approach;
+    let mut change = scale(normal, normal_change);
+
+    // friction can remove at most friction * normal change of the sliding speed
+    let friction = CombineRule::combine((a.friction, a.friction_combine), (b.friction, b.friction_combine));
+    let sliding = sub(relative, scale(normal, approach));
+    if let Some(tangent) = normalize(sliding) {
+        let slowed = length(sliding).min(friction.max(0.0) * normal_change);
+        change = sub(change, scale(tangent, slowed));
+    }
+
+    (scale(change, share), scale(change, share - 1.0))
+}
+
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+    fn write_f32(&mut self, v: f32) {
+        self.write(&v.to_bits().to_le_bytes());
+    }
+    fn write_vec2(&mut self, v: [f32;2]) {
+        self.write_f32(v[0]);
+        self.write_f32(v[1]);
+    }
+    /// Everything but the callbacks
+    fn write_po(&mut self, po: &PhysObj) {
+        self.write_vec2(po.pos);
+        self.write_vec2(po.shape);
+        self.write_vec2(po.delta);
+        match po.collider {
+            Collider::Aabb => self.write(&[0]),
+            Collider::Circle { radius } => {
+                self.write(&[1]);
+                self.write_f32(radius);
+            },
+            Collider::Capsule { radius, height } => {
+                self.write(&[2]);
+                self.write_f32(radius);
+                self.write_f32(height);
+            },
+            Collider::Polygon(polygon) => {
+                self.write(&[3, polygon.verts().len() as u8]);
+                for v in polygon.verts() {
+                    self.write_vec2(*v);
+                }
+            }
+        }
+
+        let settings = &po.settings;
+        self.write(&[settings.solid as u8, settings.trigger as u8, settings.continuous as u8]);
+        self.write(&[settings.restitution_combine as u8, settings.friction_combine as u8]);
+        for v in [settings.gravity_strength, settings.terminal_velocity, settings.mass, settings.restitution, settings.friction, settings.max_slope] {
+            self.write_f32(v);
+        }
+        for bits in [settings.layer, settings.mask, settings.behaviours] {
+            self.write(&bits.to_le_bytes());
+        }
+        match settings.one_way {
+            Some(direction) => {
+                self.write(&[1]);
+                self.write_vec2(direction);
+            },
+            None => self.write(&[0])
+        }
+    }
+    fn write_joint(&mut self, joint: &Joint) {
+        self.write(joint.a.as_bytes());
+        self.write(joint.b.as_bytes());
+        self.write_vec2(joint.anchor_a);
+        self.write_vec2(joint.anchor_b);
+        match joint.length {
+            Some(length) => {
+                self.write(&[1]);
+                self.write_f32(length);
+            },
+            None => self.write(&[0])
+        }
+        self.write(joint.kind.name().as_bytes());
+        if let JointKind::Spring { stiffness, damping } = joint.kind {
+            self.write_f32(stiffness);
+            self.write_f32(damping);
+        }
+    }
+    fn write_field(&mut self, field: &ForceField) {
+        self.write_vec2(field.min);
+        self.write_vec2(field.max);
+        self.write(&field.mask.to_le_bytes());
+        match field.kind {
+            FieldKind::Gravity(v) => {
+                self.write(&[0]);
+                self.write_vec2(v);
+            },
+            FieldKind::Radial(strength) => {
+                self.write(&[1]);
+                self.write_f32(strength);
+            },
+            FieldKind::Wind(v) => {
+                self.write(&[2]);
+                self.write_vec2(v);
+            },
+            FieldKind::Drag(amount) => {
+                self.write(&[3]);
+                self.write_f32(amount);
+            }
+        }
+    }
+}
+
+
+/// Bits at `key` from a number, a name or an array of either, with a problem for
+/// each name `bit` doesn't know. None if it's missing or has problems
+fn named_bits(data: &serde_json::Map<String, serde_json::Value>, key: &str, kind: &str, bit: impl Fn(&str) -> Option<u32>, problems: &mut Vec<SceneProblem>) -> Option<u32> {
+    let found = problems.len();
+    let value_bits = |value: &serde_json::Value, path: String, problems: &mut Vec<SceneProblem>| match value {
+        serde_json::Value::Number(bits) => bits.as_u64().map(|bits| bits as u32).unwrap_or_else(|| {
+            problems.push(SceneProblem::new(path, "expected bits"));
+            0
+        }),
+        serde_json::Value::String(name) => bit(name).unwrap_or_else(|| {
+            problems.push(SceneProblem::new(path, format!("unknown {} \"{}\"", kind, name)));
+            0
+        }),
+        _ => {
+            problems.push(SceneProblem::new(path, format!("expected a {} name or bits", kind)));
+            0
+        }
+    };
+
+    let bits = match data.get(key)? {
+        serde_json::Value::Array(values) => values.iter().enumerate().fold(0, |bits, (i, v)| bits | value_bits(v, format!("{}[{}]", key, i), problems)),
+        value => value_bits(value, key.to_string(), problems)
+    };
+    if problems.len() > found {
+        return None;
+    }
+    Some(bits)
+}
+
+
+/// Area shared by the bounds of a and b
+fn overlap_area(a: &PhysObj, b: &PhysObj) -> f32 {
+    let (a_min, a_max) = a.bounds();
+    let (b_min, b_max) = b.bounds();
+    let width = a_max[0].min(b_max[0]) - a_min[0].max(b_min[0]);
+    let height = a_max[1].min(b_max[1]) - a_min[1].max(b_min[1]);
+    width.max(0.0) * height.max(0.0)
+}
+
+
+/// Dynamic object being resolved for the current tick
+struct Body {
+    uuid: Uuid,
+    start: PhysObj,
+    /// Resolved copy of start, delta is the distance moved this tick
+    po: PhysObj,
+    velocity: [f32;2],
+    /// Triggers touched this tick (or touching this if it's the trigger)
+    triggers: Vec<Uuid>,
+    /// Static this was stopped at by a continuous sweep
+    swept: Option<Uuid>,
+    /// Kinematic this is standing on
+    carried: Option<Uuid>,
+    /// Touches that run behaviours, both ways round
+    contacts: Vec<Contact>,
+    /// Where this was blocked, for the contact summary and debug drawing
+    touches: Vec<Touch>
+}
+
+/// Solid that blocked a body this tick
+struct Touch {
+    uuid: Uuid,
+    point: [f32;2],
+    /// Pointing away from the solid
+    normal: [f32;2],
+    /// Velocity of the solid, per td like the delta of a dynamic
+    velocity: [f32;2]
+}
+
+impl Body {
+    fn new(uuid: Uuid, po: PhysObj, td: f32) -> Self {
+        Self {
+            uuid: uuid,
+            start: po,
+            po: PhysObj { delta: scale(po.delta, td), ..po },
+            velocity: [0.0,0.0],
+            triggers: Vec::new(),
+            swept: None,
+            carried: None,
+            contacts: Vec::new(),
+            touches: Vec::new()
+        }
+    }
+    fn push(&mut self, push: [f32;2]) {
+        self.po.pos[0] += push[0];
+        self.po.pos[1] += push[1];
+    }
+    /// Records where this was blocked by `other`
+    fn touch(&mut self, other_uuid: Uuid, other: &PhysObj, normal: [f32;2], velocity: [f32;2]) {
+        let core = other.core();
+        let point = core.closest_point(self.po.core().centre());
+        self.touches.push(Touch { uuid: other_uuid, point: add(point, scale(normal, core.radius)), normal: normal, velocity: velocity });
+    }
+    /// What this was blocked by this tick, `up` is against gravity
+    fn summary(&self, up: [f32;2]) -> ContactSummary {
+        let mut summary = ContactSummary::default();
+        for touch in &self.touches {
+            summary.add(touch.uuid, touch.normal, touch.velocity, up, self.po.settings.max_slope);
+        }
+        summary
+    }
+    /// Records a touch for the behaviours of either object, before it changes the velocity of this
+    fn contact(&mut self, other_uuid: Uuid, other: &PhysObj, other_velocity: [f32;2]) {
+        let velocity = self.current_velocity();
+        if self.po.settings.behaviours != 0 {
+            self.contacts.push(Contact { uuid: self.uuid, po: self.po, velocity: velocity, other_uuid: other_uuid, other: *other, other_velocity: other_velocity });
+        }
+        if other.settings.behaviours != 0 {
+            self.contacts.push(Contact { uuid: other_uuid, po: *other, velocity: other_velocity, other_uuid: self.uuid, other: self.po, other_velocity: velocity });
+        }
+    }
+    /// Moves with the first kinematic this is standing on
+    fn ride(&mut self, kinematics: &BTreeMap<Uuid, Kinematic>) {
+        let probe = PhysObj { delta: [0.0, -RIDE_DISTANCE], ..self.start };
+        for (k_uuid, kinematic) in kinematics {
+            let platform = PhysObj { delta: [0.0, 0.0], ..kinematic.po };
+            if !platform.settings.solid || platform.settings.trigger || !probe.interacts_with(&platform) || !probe.intersects(&platform) {
+                continue;
+            }
+            // only from above, the push out of the probe is upwards
+            if probe.exclusive_delta(&platform)[1] > 0.0 {
+                self.push(kinematic.po.delta);
+                self.carried = Some(*k_uuid);
+                return;
+            }
+        }
+    }
+    /// Velocity including changes made this tick
+    fn current_velocity(&self) -> [f32;2] {
+        add(self.start.delta, self.velocity)
+    }
+    /// False if `other` is one way and this is passing through it from the open side
+    fn blocked_by_one_way(&self, other: &PhysObj) -> bool {
+        let up = match other.settings.one_way {
+            Some(up) => up,
+            None => return true
+        };
+        if dot(self.po.exclusive_delta(other), up) <= 0.0 || dot(self.current_velocity(), up) > 0.0 {
+            return false;
+        }
+
+        // objects already part way through at the start of the tick keep going
+        let before = PhysObj { delta: [0.0, 0.0], ..self.start };
+        !before.intersects(other) || dot(before.exclusive_delta(other), up) <= ONE_WAY_SLOP
+    }
+    /// Events that move the object to its resolved position and velocity
+    fn resolution(&self) -> Vec<RoutedEvent<PhysEvent>> {
+        let mut queue = Vec::new();
+
+        let push = [self.po.pos[0] - self.start.pos[0], self.po.pos[1] - self.start.pos[1]];
+        if push != [0.0, 0.0] {
+            queue.push(RoutedEvent(Some(self.uuid), PhysEvent::ModPos(push)));
+        }
+        if self.velocity != [0.0, 0.0] {
+            queue.push(RoutedEvent(Some(self.uuid), PhysEvent::ModVelocity(self.velocity)));
+        }
+
         queue
     }
 }
 
-const GRAVITY: f32 = -0.005;
\ No newline at end of file
+/// Default world gravity, straight down
+const GRAVITY: f32 = -0.005;
+const DEFAULT_LAYER: u32 = 1;
+/// Slowest approach speed that still bounces
+const RESTITUTION_THRESHOLD: f32 = 0.01;
+/// How far into a one way object something can start a tick and still be stopped by it
+const ONE_WAY_SLOP: f32 = 0.05;
+/// How far above a kinematic something can be and still ride it
+const RIDE_DISTANCE: f32 = 0.05;
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
\ No newline at end of file
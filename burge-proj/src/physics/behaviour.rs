@@ -0,0 +1,105 @@
+use std::rc::Rc;
+
+use uuid::Uuid;
+
+use crate::event::RoutedEvent;
+use super::shape::{dot, normalize, scale};
+use super::{PhysEvent, PhysObj};
+
+
+/// Collision behaviour, returns events to send (usually to `contact.other_uuid`)
+pub type Behaviour = Rc<dyn Fn(&Contact) -> Vec<RoutedEvent<PhysEvent>>>;
+
+
+/// Touch between an object with behaviours (uuid, po) and another object.
+/// Velocities are from before the touch, per td like the delta of a dynamic
+#[derive(Clone, Copy)]
+pub struct Contact {
+    pub uuid: Uuid,
+    pub po: PhysObj,
+    pub velocity: [f32;2],
+    pub other_uuid: Uuid,
+    pub other: PhysObj,
+    pub other_velocity: [f32;2]
+}
+
+
+/// Named behaviours, the one at index i is bit 1 << i of POSettings::behaviours.
+/// Starts with "bounce" and "stop", which act on whatever touches the object
+#[derive(Clone)]
+pub struct Behaviours {
+    named: Vec<(String, Behaviour)>
+}
+
+impl std::default::Default for Behaviours {
+    fn default() -> Self {
+        let mut behaviours = Self { named: Vec::new() };
+        behaviours.register("bounce", |contact| {
+            // reflect the velocity of the other object off this one
+            let normal = normalize(contact.other.exclusive_delta(&contact.po)).unwrap_or([0.0, 1.0]);
+            let approach = dot(contact.other_velocity, normal);
+            if approach >= 0.0 {
+                return Vec::new();
+            }
+            // solid pairs already have the approach removed by the contact
+            let (a, b) = (contact.po.settings, contact.other.settings);
+            let blocked = a.solid && b.solid && !a.trigger && !b.trigger;
+            let bounce = if blocked { -approach } else { -2.0*approach };
+            vec![RoutedEvent(Some(contact.other_uuid), PhysEvent::ModVelocity(scale(normal, bounce)))]
+        });
+        behaviours.register("stop", |contact| {
+            vec![RoutedEvent(Some(contact.other_uuid), PhysEvent::ModVelocity(scale(contact.other_velocity, -1.0)))]
+        });
+        behaviours
+    }
+}
+
+impl Behaviours {
+    /// Adds or replaces a named behaviour, returns its bit (None when all 32 are taken)
+    pub fn register(&mut self, name: &str, behaviour: impl Fn(&Contact) -> Vec<RoutedEvent<PhysEvent>> + 'static) -> Option<u32> {
+        let behaviour: Behaviour = Rc::new(behaviour);
+        if let Some(i) = self.named.iter().position(|(n, _)| n == name) {
+            self.named[i].1 = behaviour;
+            return Some(1 << i);
+        }
+        if self.named.len() >= 32 {
+            return None;
+        }
+        self.named.push((name.to_string(), behaviour));
+        Some(1 << (self.named.len() - 1))
+    }
+    /// Bit for a named behaviour
+    pub fn bit(&self, name: &str) -> Option<u32> {
+        self.named.iter().position(|(n, _)| n == name).map(|i| 1 << i)
+    }
+    /// Behaviour bits from JSON: a number, a behaviour name, or an array of either
+    pub fn bits(&self, value: &serde_json::Value) -> Option<u32> {
+        match value {
+            serde_json::Value::Number(bits) => bits.as_u64().map(|bits| bits as u32),
+            serde_json::Value::String(name) => self.bit(name),
+            serde_json::Value::Array(values) => {
+                let mut bits = 0;
+                for v in values {
+                    bits |= self.bits(v)?;
+                }
+                Some(bits)
+            },
+            _ => None
+        }
+    }
+    /// Behaviour bits as names for bits, with a number for any bits without one
+    pub fn save_bits(&self, bits: u32) -> serde_json::Value {
+        let mut saved: Vec<serde_json::Value> = self.names(bits).into_iter().map(|name| name.into()).collect();
+        let unnamed = bits & !((1u64 << self.named.len()) - 1) as u32;
+        if unnamed != 0 {
+            saved.push(unnamed.into());
+        }
+        saved.into()
+    }
+    pub fn names(&self, bits: u32) -> Vec<String> {
+        self.named.iter().enumerate().filter(|(i, _)| bits & (1 << i) != 0).map(|(_, (n, _))| n.clone()).collect()
+    }
+    pub fn matching(&self, bits: u32) -> Vec<Behaviour> {
+        self.named.iter().enumerate().filter(|(i, _)| bits & (1 << i) != 0).map(|(_, (_, b))| b.clone()).collect()
+    }
+}
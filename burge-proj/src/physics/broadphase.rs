@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+
+/// Uniform grid of square cells, each listing the objects overlapping it
+#[derive(Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32,i32), Vec<Uuid>>,
+    ranges: HashMap<Uuid, [i32;4]>,
+    /// Objects covering more than MAX_CELLS cells, checked by range on every query instead
+    large: Vec<Uuid>
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size,
+            cells: HashMap::new(),
+            ranges: HashMap::new(),
+            large: Vec::new()
+        }
+    }
+    pub fn insert(&mut self, uuid: Uuid, bounds: ([f32;2],[f32;2])) {
+        self.remove(&uuid);
+
+        let range = self.range(bounds);
+        if area(range) > MAX_CELLS {
+            self.large.push(uuid);
+        } else {
+            for x in range[0]..=range[2] {
+                for y in range[1]..=range[3] {
+                    self.cells.entry((x,y)).or_default().push(uuid);
+                }
+            }
+        }
+        self.ranges.insert(uuid, range);
+    }
+    pub fn remove(&mut self, uuid: &Uuid) {
+        if let Some(range) = self.ranges.remove(uuid) {
+            if area(range) > MAX_CELLS {
+                self.large.retain(|u| u != uuid);
+                return;
+            }
+            for x in range[0]..=range[2] {
+                for y in range[1]..=range[3] {
+                    if let Some(cell) = self.cells.get_mut(&(x,y)) {
+                        cell.retain(|u| u != uuid);
+                        if cell.is_empty() {
+                            self.cells.remove(&(x,y));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.ranges.clear();
+        self.large.clear();
+    }
+    /// Every object sharing a cell with `bounds`, sorted and without duplicates
+    pub fn query(&self, bounds: ([f32;2],[f32;2])) -> Vec<Uuid> {
+        let mut found = Vec::new();
+
+        let range = self.range(bounds);
+        if area(range) > self.cells.len() as i64 {
+            // huge ranges, like long raycasts, check the occupied cells instead
+            for (&(x, y), cell) in &self.cells {
+                if x >= range[0] && x <= range[2] && y >= range[1] && y <= range[3] {
+                    found.extend_from_slice(cell);
+                }
+            }
+        } else {
+            for x in range[0]..=range[2] {
+                for y in range[1]..=range[3] {
+                    if let Some(cell) = self.cells.get(&(x,y)) {
+                        found.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+
+        for uuid in &self.large {
+            let other = self.ranges[uuid];
+            if other[0] <= range[2] && range[0] <= other[2] && other[1] <= range[3] && range[1] <= other[3] {
+                found.push(*uuid);
+            }
+        }
+
+        found.sort();
+        found.dedup();
+        found
+    }
+    /// (min, max) of every cell with something in it
+    pub fn occupied_cells(&self) -> Vec<([f32;2],[f32;2])> {
+        self.cells.keys().map(|(x, y)| {
+            let min = [*x as f32 * self.cell_size, *y as f32 * self.cell_size];
+            (min, [min[0] + self.cell_size, min[1] + self.cell_size])
+        }).collect()
+    }
+
+    /// Inclusive cell range [min_x, min_y, max_x, max_y] covered by `bounds`
+    fn range(&self, bounds: ([f32;2],[f32;2])) -> [i32;4] {
+        let (min, max) = bounds;
+        [
+            (min[0] / self.cell_size).floor() as i32,
+            (min[1] / self.cell_size).floor() as i32,
+            (max[0] / self.cell_size).floor() as i32,
+            (max[1] / self.cell_size).floor() as i32
+        ]
+    }
+}
+
+/// Number of cells in an inclusive cell range
+fn area(range: [i32;4]) -> i64 {
+    let width = (range[2] as i64 - range[0] as i64 + 1).max(0);
+    let height = (range[3] as i64 - range[1] as i64 + 1).max(0);
+    width.saturating_mul(height)
+}
+
+
+/// Most cells one object is listed in, past this it goes in SpatialHash::large
+const MAX_CELLS: i64 = 1024;
@@ -0,0 +1,130 @@
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use super::shape::dot;
+use super::Side;
+use crate::scene::{bool_field, uuid_field, vec2_field, SceneProblem};
+
+pub const MAX_CONTACT_NORMALS: usize = 8;
+
+
+/// Everything a dynamic was blocked by in one tick, sent to it as PhysEvent::Contacts
+/// once it's resolved. Sides are sides of the dynamic, like PhysEvent::Blocked
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ContactSummary {
+    /// Standing on something no steeper than its max_slope, measured against gravity
+    pub grounded: bool,
+    /// What it's standing on, the flattest if there are several
+    pub ground: Option<Uuid>,
+    pub ground_normal: [f32;2],
+    /// Velocity of the ground, per td like PhysObj::delta
+    pub ground_velocity: [f32;2],
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+    normals: [[f32;2]; MAX_CONTACT_NORMALS],
+    len: usize
+}
+
+impl ContactSummary {
+    /// Normals of every contact, pointing away from what was touched.
+    /// Contacts past MAX_CONTACT_NORMALS only set the sides
+    pub fn normals(&self) -> &[[f32;2]] {
+        &self.normals[..self.len]
+    }
+    pub fn touching(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+            Side::Top => self.top,
+            Side::Bottom => self.bottom
+        }
+    }
+    pub fn on_wall(&self) -> bool {
+        self.left || self.right
+    }
+    pub fn on_ceiling(&self) -> bool {
+        self.top
+    }
+    /// Adds a contact with `normal`, `up` is against gravity
+    pub(super) fn add(&mut self, uuid: Uuid, normal: [f32;2], velocity: [f32;2], up: [f32;2], max_slope: f32) {
+        if self.len < MAX_CONTACT_NORMALS {
+            self.normals[self.len] = normal;
+            self.len += 1;
+        }
+        match Side::from_push(normal) {
+            Some(Side::Left) => self.left = true,
+            Some(Side::Right) => self.right = true,
+            Some(Side::Top) => self.top = true,
+            Some(Side::Bottom) => self.bottom = true,
+            None => ()
+        }
+
+        let flatness = dot(normal, up);
+        let flatter = !self.grounded || flatness > dot(self.ground_normal, up);
+        if flatness > 0.0 && flatness >= max_slope.to_radians().cos() && flatter {
+            self.grounded = true;
+            self.ground = Some(uuid);
+            self.ground_normal = normal;
+            self.ground_velocity = velocity;
+        }
+    }
+    pub fn save(&self) -> Value {
+        json!({
+            "grounded": self.grounded,
+            "ground": self.ground.map(|uuid| uuid.to_string()),
+            "ground_normal": self.ground_normal,
+            "ground_velocity": self.ground_velocity,
+            "left": self.left,
+            "right": self.right,
+            "top": self.top,
+            "bottom": self.bottom,
+            "normals": self.normals()
+        })
+    }
+    /// Summary in the format written by save
+    pub fn load(data: &Map<String, Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &Map<String, Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let mut flag = |key: &str| bool_field(data, key, &mut problems).unwrap_or(false);
+
+        let mut summary = Self {
+            grounded: flag("grounded"),
+            left: flag("left"),
+            right: flag("right"),
+            top: flag("top"),
+            bottom: flag("bottom"),
+            ..Self::default()
+        };
+        // save writes null for no ground
+        if !matches!(data.get("ground"), Some(Value::Null)) {
+            summary.ground = uuid_field(data, "ground", &mut problems);
+        }
+        summary.ground_normal = vec2_field(data, "ground_normal", &mut problems).unwrap_or([0.0, 0.0]);
+        summary.ground_velocity = vec2_field(data, "ground_velocity", &mut problems).unwrap_or([0.0, 0.0]);
+        match data.get("normals") {
+            Some(Value::Array(values)) => for (i, normal) in values.iter().enumerate() {
+                match crate::try_vec2!(normal) {
+                    Some(normal) if summary.len < MAX_CONTACT_NORMALS => {
+                        summary.normals[summary.len] = normal;
+                        summary.len += 1;
+                    },
+                    Some(_) => (),
+                    None => problems.push(SceneProblem::new(format!("normals[{}]", i), "expected [x, y]"))
+                }
+            },
+            Some(_) => problems.push(SceneProblem::new("normals", "expected an array of [x, y]")),
+            None => ()
+        }
+
+        if problems.is_empty() {
+            Ok(summary)
+        } else {
+            Err(problems)
+        }
+    }
+}
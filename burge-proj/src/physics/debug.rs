@@ -0,0 +1,114 @@
+use std::f32::consts::TAU;
+
+use super::shape::{add, dot, scale, Core};
+use super::{POMComponent, PhysObj};
+
+
+/// Line in world space for the physics debug overlay
+#[derive(Clone, Copy, Debug)]
+pub struct DebugLine {
+    pub from: [f32;2],
+    pub to: [f32;2],
+    pub color: [f32;4]
+}
+
+
+impl POMComponent {
+    /// Outlines, velocities, contacts, joints, force fields and broadphase cells from the last tick
+    pub fn debug_lines(&self) -> Vec<DebugLine> {
+        let world = self.world.borrow();
+        let mut lines = Vec::new();
+
+        if let Some(broadphase) = &world.broadphase {
+            for (min, max) in broadphase.occupied_cells() {
+                outline(&mut lines, &[min, [max[0], min[1]], max, [min[0], max[1]]], CELL_COLOR);
+            }
+        }
+
+        for (_, field) in &world.fields {
+            let (min, max) = (field.min, field.max);
+            outline(&mut lines, &[min, [max[0], min[1]], max, [min[0], max[1]]], FIELD_COLOR);
+        }
+
+        for uuid in world.statics.keys() {
+            let po = &world.static_po(uuid);
+            outline(&mut lines, &outline_points(po), if po.settings.trigger { TRIGGER_COLOR } else { STATIC_COLOR });
+        }
+        for kinematic in world.kinematics.values() {
+            outline(&mut lines, &outline_points(&kinematic.po), KINEMATIC_COLOR);
+        }
+        for (_, po) in &world.dynamics {
+            outline(&mut lines, &outline_points(po), if po.settings.trigger { TRIGGER_COLOR } else { DYNAMIC_COLOR });
+            let centre = po.core().centre();
+            lines.push(DebugLine { from: centre, to: add(centre, scale(po.delta, VELOCITY_SCALE)), color: VELOCITY_COLOR });
+        }
+
+        for (point, normal) in &world.contacts {
+            let [x, y] = *point;
+            lines.push(DebugLine { from: [x - CONTACT_SIZE, y - CONTACT_SIZE], to: [x + CONTACT_SIZE, y + CONTACT_SIZE], color: CONTACT_COLOR });
+            lines.push(DebugLine { from: [x - CONTACT_SIZE, y + CONTACT_SIZE], to: [x + CONTACT_SIZE, y - CONTACT_SIZE], color: CONTACT_COLOR });
+            lines.push(DebugLine { from: *point, to: add(*point, scale(*normal, NORMAL_LENGTH)), color: CONTACT_COLOR });
+        }
+
+        for (_, joint) in &world.joints {
+            let anchor = |uuid, offset| {
+                if uuid == uuid::Uuid::nil() {
+                    return Some(offset);
+                }
+                let po = match world.statics.get(&uuid) {
+                    Some(_) => world.static_po(&uuid),
+                    None => *world.kinematics.get(&uuid).map(|k| &k.po)
+                        .or_else(|| world.dynamics.iter().find(|(d_uuid, _)| *d_uuid == uuid).map(|(_, po)| po))?
+                };
+                Some(add(add(po.pos, po.delta), offset))
+            };
+            if let (Some(from), Some(to)) = (anchor(joint.a, joint.anchor_a), anchor(joint.b, joint.anchor_b)) {
+                lines.push(DebugLine { from: from, to: to, color: JOINT_COLOR });
+            }
+        }
+
+        lines
+    }
+}
+
+
+/// Closed loop through `points`
+fn outline(lines: &mut Vec<DebugLine>, points: &[[f32;2]], color: [f32;4]) {
+    for i in 0..points.len() {
+        lines.push(DebugLine { from: points[i], to: points[(i+1)%points.len()], color: color });
+    }
+}
+
+/// Points around a collider, rounded ones are sampled in CIRCLE_SEGMENTS directions
+fn outline_points(po: &PhysObj) -> Vec<[f32;2]> {
+    let core: Core = po.core();
+    if core.radius <= 0.0 {
+        return core.verts().to_vec();
+    }
+
+    (0..CIRCLE_SEGMENTS).map(|i| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+        let direction = [angle.cos(), angle.sin()];
+        let furthest = core.verts().iter().copied()
+            .max_by(|a, b| dot(*a, direction).total_cmp(&dot(*b, direction)))
+            .unwrap_or(core.centre());
+        add(furthest, scale(direction, core.radius))
+    }).collect()
+}
+
+
+const CIRCLE_SEGMENTS: usize = 24;
+/// Velocity lines show how far an object moves in this many ticks
+const VELOCITY_SCALE: f32 = 10.0;
+const CONTACT_SIZE: f32 = 0.05;
+const NORMAL_LENGTH: f32 = 0.3;
+
+const CELL_COLOR: [f32;4] = [0.3, 0.3, 0.3, 0.5];
+const STATIC_COLOR: [f32;4] = [1.0, 1.0, 1.0, 1.0];
+const KINEMATIC_COLOR: [f32;4] = [0.3, 0.5, 1.0, 1.0];
+const DYNAMIC_COLOR: [f32;4] = [0.2, 1.0, 0.2, 1.0];
+const TRIGGER_COLOR: [f32;4] = [1.0, 1.0, 0.2, 1.0];
+const VELOCITY_COLOR: [f32;4] = [1.0, 0.2, 0.2, 1.0];
+const CONTACT_COLOR: [f32;4] = [1.0, 0.2, 1.0, 1.0];
+const JOINT_COLOR: [f32;4] = [0.2, 1.0, 1.0, 1.0];
+const FIELD_COLOR: [f32;4] = [1.0, 0.6, 0.2, 0.7];
@@ -0,0 +1,127 @@
+use super::shape::{add, normalize, scale, sub};
+use crate::scene::{f32_field, vec2_field, SceneProblem};
+
+
+/// Box affecting every object whose centre is inside it and whose layer is in `mask`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForceField {
+    pub min: [f32;2],
+    pub max: [f32;2],
+    pub mask: u32,
+    pub kind: FieldKind
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    /// Replaces the world gravity, scaled by gravity_strength like it
+    Gravity([f32;2]),
+    /// Gravity of this strength towards the centre of the field, planet style
+    Radial(f32),
+    /// Constant force, lighter objects are pushed more
+    Wind([f32;2]),
+    /// Share of velocity lost each tick
+    Drag(f32)
+}
+
+impl ForceField {
+    pub fn new(min: [f32;2], max: [f32;2], kind: FieldKind) -> Self {
+        Self {
+            min: min,
+            max: max,
+            mask: u32::MAX,
+            kind: kind
+        }
+    }
+    pub fn contains(&self, point: [f32;2]) -> bool {
+        point[0] >= self.min[0] && point[0] <= self.max[0] && point[1] >= self.min[1] && point[1] <= self.max[1]
+    }
+    pub fn centre(&self) -> [f32;2] {
+        scale(add(self.min, self.max), 0.5)
+    }
+    /// Gravity at `point` if this field overrides it
+    pub fn gravity(&self, point: [f32;2]) -> Option<[f32;2]> {
+        match self.kind {
+            FieldKind::Gravity(gravity) => Some(gravity),
+            FieldKind::Radial(strength) => Some(normalize(sub(self.centre(), point)).map_or([0.0, 0.0], |d| scale(d, strength))),
+            _ => None
+        }
+    }
+    pub fn save(&self) -> serde_json::Value {
+        let mut data = serde_json::json!({
+            "min": self.min,
+            "max": self.max,
+            "mask": self.mask
+        });
+        match self.kind {
+            FieldKind::Gravity(v) => { data["kind"] = "gravity".into(); data["vec"] = serde_json::json!(v); },
+            FieldKind::Radial(strength) => { data["kind"] = "radial".into(); data["strength"] = strength.into(); },
+            FieldKind::Wind(v) => { data["kind"] = "wind".into(); data["vec"] = serde_json::json!(v); },
+            FieldKind::Drag(amount) => { data["kind"] = "drag".into(); data["amount"] = amount.into(); }
+        }
+        data
+    }
+    /// Field from JSON, "mask" must be a number here (see POMSettings::layer_bits for names)
+    pub fn load(data: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let required_vec2 = |key: &str, problems: &mut Vec<SceneProblem>| {
+            let v = vec2_field(data, key, problems);
+            if !data.contains_key(key) {
+                problems.push(SceneProblem::new(key, "missing"));
+            }
+            v
+        };
+        let required_f32 = |key: &str, problems: &mut Vec<SceneProblem>| {
+            let v = f32_field(data, key, problems);
+            if !data.contains_key(key) {
+                problems.push(SceneProblem::new(key, "missing"));
+            }
+            v
+        };
+
+        let kind = match data.get("kind") {
+            Some(serde_json::Value::String(kind)) => match kind.as_str() {
+                "gravity" => required_vec2("vec", &mut problems).map(FieldKind::Gravity),
+                "radial" => required_f32("strength", &mut problems).map(FieldKind::Radial),
+                "wind" => required_vec2("vec", &mut problems).map(FieldKind::Wind),
+                "drag" => required_f32("amount", &mut problems).map(FieldKind::Drag),
+                _ => {
+                    problems.push(SceneProblem::new("kind", format!("unknown field kind \"{}\"", kind)));
+                    None
+                }
+            },
+            Some(_) => {
+                problems.push(SceneProblem::new("kind", "expected a string"));
+                None
+            },
+            None => {
+                problems.push(SceneProblem::new("kind", "missing"));
+                None
+            }
+        };
+        let min = required_vec2("min", &mut problems);
+        let max = required_vec2("max", &mut problems);
+        let mask = match data.get("mask") {
+            Some(mask) => match mask.as_u64() {
+                Some(mask) => Some(mask as u32),
+                None => {
+                    problems.push(SceneProblem::new("mask", "expected layer bits"));
+                    None
+                }
+            },
+            None => None
+        };
+
+        match (min, max, kind) {
+            (Some(min), Some(max), Some(kind)) if problems.is_empty() => {
+                let mut field = Self::new(min, max, kind);
+                field.mask = mask.unwrap_or(u32::MAX);
+                Ok(field)
+            },
+            _ => Err(problems)
+        }
+    }
+}
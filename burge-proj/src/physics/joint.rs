@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+
+use uuid::Uuid;
+
+use super::shape::{add, dot, length, normalize, scale, sub};
+use super::kinematic::Kinematic;
+use super::{Body, PhysObj};
+use crate::scene::{f32_field, uuid_field, vec2_field, SceneProblem};
+
+
+/// Link between anchor points on two objects, see JointKind
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Joint {
+    pub a: Uuid,
+    /// Nil to join `a` to the fixed point `anchor_b`
+    pub b: Uuid,
+    /// Offsets from the pos of each object
+    pub anchor_a: [f32;2],
+    pub anchor_b: [f32;2],
+    /// Rest length, None takes the distance between the anchors when first solved
+    pub length: Option<f32>,
+    pub kind: JointKind
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointKind {
+    /// Keeps the anchors `length` apart
+    Distance,
+    /// Pulls the anchors towards `length` apart
+    Spring { stiffness: f32, damping: f32 },
+    /// Keeps the anchors at most `length` apart
+    Rope,
+    /// Keeps the anchors together
+    Pin
+}
+
+impl JointKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JointKind::Distance => "distance",
+            JointKind::Spring { .. } => "spring",
+            JointKind::Rope => "rope",
+            JointKind::Pin => "pin"
+        }
+    }
+}
+
+impl Joint {
+    pub fn new(a: Uuid, b: Uuid, kind: JointKind) -> Self {
+        Self {
+            a: a,
+            b: b,
+            anchor_a: [0.0, 0.0],
+            anchor_b: [0.0, 0.0],
+            length: None,
+            kind: kind
+        }
+    }
+    pub fn with_anchors(mut self, anchor_a: [f32;2], anchor_b: [f32;2]) -> Self {
+        self.anchor_a = anchor_a;
+        self.anchor_b = anchor_b;
+        self
+    }
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+    /// Joint from JSON, "a" and "kind" are required
+    pub fn load(data: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let a = uuid_field(data, "a", &mut problems);
+        if !data.contains_key("a") {
+            problems.push(SceneProblem::new("a", "missing"));
+        }
+        let b = uuid_field(data, "b", &mut problems);
+        let anchor_a = vec2_field(data, "anchor_a", &mut problems);
+        let anchor_b = vec2_field(data, "anchor_b", &mut problems);
+        // save writes null for a length not taken yet
+        let length = match data.get("length") {
+            Some(serde_json::Value::Null) => None,
+            _ => f32_field(data, "length", &mut problems)
+        };
+
+        let kind = match data.get("kind") {
+            Some(serde_json::Value::String(kind)) => match kind.as_str() {
+                "distance" => Some(JointKind::Distance),
+                "spring" => Some(JointKind::Spring {
+                    stiffness: f32_field(data, "stiffness", &mut problems).unwrap_or(DEFAULT_STIFFNESS),
+                    damping: f32_field(data, "damping", &mut problems).unwrap_or(DEFAULT_DAMPING)
+                }),
+                "rope" => Some(JointKind::Rope),
+                "pin" => Some(JointKind::Pin),
+                _ => {
+                    problems.push(SceneProblem::new("kind", format!("unknown joint kind \"{}\"", kind)));
+                    None
+                }
+            },
+            Some(_) => {
+                problems.push(SceneProblem::new("kind", "expected a string"));
+                None
+            },
+            None => {
+                problems.push(SceneProblem::new("kind", "missing"));
+                None
+            }
+        };
+
+        match (a, kind) {
+            (Some(a), Some(kind)) if problems.is_empty() => Ok(Self {
+                a: a,
+                b: b.unwrap_or(Uuid::nil()),
+                anchor_a: anchor_a.unwrap_or([0.0, 0.0]),
+                anchor_b: anchor_b.unwrap_or([0.0, 0.0]),
+                length: length,
+                kind: kind
+            }),
+            _ => Err(problems)
+        }
+    }
+    /// In the format read by load
+    pub fn save(&self) -> serde_json::Value {
+        let mut data = serde_json::json!({
+            "a": self.a.to_string(),
+            "b": self.b.to_string(),
+            "anchor_a": self.anchor_a,
+            "anchor_b": self.anchor_b,
+            "length": self.length,
+            "kind": self.kind.name()
+        });
+        if let JointKind::Spring { stiffness, damping } = self.kind {
+            data["stiffness"] = stiffness.into();
+            data["damping"] = damping.into();
+        }
+        data
+    }
+}
+
+
+/// One end of a joint during solving
+#[derive(Clone, Copy)]
+enum End {
+    Body(usize),
+    /// Statics, kinematics and fixed points, as (anchor position, velocity)
+    Fixed([f32;2], [f32;2])
+}
+
+/// Moves and slows bodies so their joints hold, statics, kinematics and fixed points don't move.
+/// Joints with an end that isn't in the world this tick are skipped
+pub(super) fn solve_joints(joints: &mut [(Uuid, Joint)], bodies: &mut [Body], statics: &HashMap<Uuid, PhysObj>, kinematics: &BTreeMap<Uuid, Kinematic>, td: f32) {
+    let index: HashMap<Uuid, usize> = bodies.iter().enumerate().map(|(i, body)| (body.uuid, i)).collect();
+    let end = |uuid: Uuid, anchor: [f32;2]| {
+        if uuid.is_nil() {
+            Some(End::Fixed(anchor, [0.0, 0.0]))
+        } else if let Some(i) = index.get(&uuid) {
+            Some(End::Body(*i))
+        } else if let Some(k) = kinematics.get(&uuid) {
+            // where it will be at the end of the tick, like bodies
+            Some(End::Fixed(add(add(k.po.pos, k.po.delta), anchor), k.tick_velocity(td)))
+        } else {
+            statics.get(&uuid).map(|s| End::Fixed(add(s.pos, anchor), s.delta))
+        }
+    };
+
+    // several passes so chains of joints settle together
+    for _ in 0..JOINT_ITERATIONS {
+        for (_, joint) in joints.iter_mut() {
+            let (a, b) = match (end(joint.a, joint.anchor_a), end(joint.b, joint.anchor_b)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => continue
+            };
+            let state = |end: End, anchor: [f32;2]| match end {
+                End::Body(i) => (add(add(bodies[i].po.pos, bodies[i].po.delta), anchor), bodies[i].current_velocity(), bodies[i].po.settings.mass.max(0.0)),
+                End::Fixed(point, velocity) => (point, velocity, f32::INFINITY)
+            };
+            let (a_point, a_velocity, a_mass) = state(a, joint.anchor_a);
+            let (b_point, b_velocity, b_mass) = state(b, joint.anchor_b);
+
+            // how much of each correction a takes, the lighter end moves more
+            let a_share = match (a, b) {
+                (End::Fixed(..), End::Fixed(..)) => continue,
+                (End::Fixed(..), _) => 0.0,
+                (_, End::Fixed(..)) => 1.0,
+                _ if a_mass + b_mass > 0.0 => b_mass / (a_mass + b_mass),
+                _ => 0.5
+            };
+
+            let offset = sub(a_point, b_point);
+            let distance = length(offset);
+            let relative = sub(a_velocity, b_velocity);
+
+            // (position correction, velocity change) for a, b gets the opposite
+            let (push, change) = match joint.kind {
+                JointKind::Pin => (scale(offset, -1.0), scale(relative, -1.0)),
+                kind => {
+                    let normal = match normalize(offset) {
+                        Some(normal) => normal,
+                        None => continue
+                    };
+                    let error = distance - *joint.length.get_or_insert(distance);
+                    let approach = dot(relative, normal);
+                    match kind {
+                        JointKind::Spring { stiffness, damping } => {
+                            ([0.0, 0.0], scale(normal, (-stiffness*error - damping*approach) * td / JOINT_ITERATIONS as f32))
+                        },
+                        JointKind::Rope if error <= 0.0 => continue,
+                        JointKind::Rope => (scale(normal, -error), scale(normal, -approach.max(0.0))),
+                        _ => (scale(normal, -error), scale(normal, -approach))
+                    }
+                }
+            };
+
+            if let End::Body(i) = a {
+                bodies[i].push(scale(push, a_share));
+                bodies[i].velocity = add(bodies[i].velocity, scale(change, a_share));
+            }
+            if let End::Body(i) = b {
+                bodies[i].push(scale(push, a_share - 1.0));
+                bodies[i].velocity = add(bodies[i].velocity, scale(change, a_share - 1.0));
+            }
+        }
+    }
+}
+
+
+const JOINT_ITERATIONS: usize = 4;
+const DEFAULT_STIFFNESS: f32 = 0.05;
+const DEFAULT_DAMPING: f32 = 0.05;
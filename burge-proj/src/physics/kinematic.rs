@@ -0,0 +1,243 @@
+use super::shape::{add, length, scale, sub};
+use super::{Fnv, PhysObj};
+use crate::scene::{bool_field, f32_field, object_field, vec2_field, SceneProblem};
+
+pub const MAX_PATH_POINTS: usize = 8;
+
+
+/// Points a kinematic moves its pos between at `speed`, going back and forth
+/// along the points unless `looping`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KinematicPath {
+    points: [[f32;2]; MAX_PATH_POINTS],
+    len: usize,
+    pub speed: f32,
+    pub looping: bool
+}
+
+impl KinematicPath {
+    /// Points past MAX_PATH_POINTS are dropped
+    pub fn new(points: &[[f32;2]], speed: f32, looping: bool) -> Self {
+        let len = points.len().min(MAX_PATH_POINTS);
+        let mut path = Self {
+            points: [[0.0,0.0]; MAX_PATH_POINTS],
+            len: len,
+            speed: speed,
+            looping: looping
+        };
+        path.points[..len].copy_from_slice(&points[..len]);
+        path
+    }
+    pub fn points(&self) -> &[[f32;2]] {
+        &self.points[..self.len]
+    }
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::json!({
+            "points": self.points(),
+            "speed": self.speed,
+            "looping": self.looping
+        })
+    }
+    /// Path in the format written by save
+    pub fn load(data: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`. More than MAX_PATH_POINTS points is a problem
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let mut points = Vec::new();
+        match data.get("points").and_then(|v| v.as_array()) {
+            Some(values) => {
+                for (i, v) in values.iter().enumerate() {
+                    match crate::try_vec2!(v) {
+                        Some(v) => points.push(v),
+                        None => problems.push(SceneProblem::new(format!("points[{}]", i), "expected [x, y]"))
+                    }
+                }
+                if values.len() > MAX_PATH_POINTS {
+                    problems.push(SceneProblem::new("points", format!("at most {} points", MAX_PATH_POINTS)));
+                }
+            },
+            None => problems.push(SceneProblem::new("points", "expected an array of [x, y]"))
+        }
+        let speed = f32_field(data, "speed", &mut problems);
+        if !data.contains_key("speed") {
+            problems.push(SceneProblem::new("speed", "missing"));
+        }
+        let looping = bool_field(data, "looping", &mut problems);
+
+        match speed {
+            Some(speed) if problems.is_empty() => Ok(Self::new(&points, speed, looping.unwrap_or(false))),
+            _ => Err(problems)
+        }
+    }
+}
+
+
+/// Object moved by the POM, pushing dynamics and carrying the ones on top
+#[derive(Clone, Copy)]
+pub(super) struct Kinematic {
+    /// pos is where it is, delta is the distance it's moving this tick
+    pub po: PhysObj,
+    pub velocity: [f32;2],
+    pub path: Option<KinematicPath>,
+    /// Index of the path point being moved towards
+    target: usize,
+    backwards: bool
+}
+
+impl Kinematic {
+    /// Moved by the delta of `po` each tick
+    pub fn new(po: PhysObj) -> Self {
+        Self {
+            po: PhysObj { delta: [0.0, 0.0], ..po },
+            velocity: po.delta,
+            path: None,
+            target: 0,
+            backwards: false
+        }
+    }
+    /// Everything, path progress included, for World::hash_state
+    pub(super) fn write_hash(&self, hash: &mut Fnv) {
+        hash.write_po(&self.po);
+        hash.write_vec2(self.velocity);
+        match &self.path {
+            Some(path) => {
+                hash.write(&[1, path.points().len() as u8]);
+                for point in path.points() {
+                    hash.write_vec2(*point);
+                }
+                hash.write_f32(path.speed);
+                hash.write(&[path.looping as u8]);
+            },
+            None => hash.write(&[0])
+        }
+        hash.write(&(self.target as u32).to_le_bytes());
+        hash.write(&[self.backwards as u8]);
+    }
+    /// Replaces the object and velocity, keeping the path
+    pub fn set_po(&mut self, po: PhysObj) {
+        self.po = PhysObj { delta: [0.0, 0.0], ..po };
+        self.velocity = po.delta;
+    }
+    pub fn set_path(&mut self, path: KinematicPath) {
+        self.path = Some(path);
+        self.target = 0;
+        self.backwards = false;
+    }
+    pub fn save(&self) -> serde_json::Value {
+        serde_json::json!({
+            "po": self.po.save(),
+            "velocity": self.velocity,
+            "path": self.path.map(|path| path.save()),
+            "target": self.target,
+            "backwards": self.backwards
+        })
+    }
+    /// Kinematic in the format written by save, "po" is required
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let po = match object_field(data, "po", &mut problems).map(PhysObj::try_load) {
+            Some(Ok(po)) => Some(po),
+            Some(Err(errors)) => {
+                problems.extend(errors.into_iter().map(|p| p.within("po")));
+                None
+            },
+            None => {
+                if !data.contains_key("po") {
+                    problems.push(SceneProblem::new("po", "missing"));
+                }
+                None
+            }
+        };
+        let velocity = vec2_field(data, "velocity", &mut problems);
+        // save writes null for no path
+        let path = match data.get("path") {
+            Some(serde_json::Value::Null) => None,
+            _ => match object_field(data, "path", &mut problems).map(KinematicPath::try_load) {
+                Some(Ok(path)) => Some(path),
+                Some(Err(errors)) => {
+                    problems.extend(errors.into_iter().map(|p| p.within("path")));
+                    None
+                },
+                None => None
+            }
+        };
+        let target = match data.get("target") {
+            Some(target) => match target.as_u64() {
+                Some(target) => Some(target as usize),
+                None => {
+                    problems.push(SceneProblem::new("target", "expected a point index"));
+                    None
+                }
+            },
+            None => None
+        };
+        let backwards = bool_field(data, "backwards", &mut problems);
+
+        match po {
+            Some(po) if problems.is_empty() => {
+                let mut kinematic = Self::new(po);
+                kinematic.velocity = velocity.unwrap_or(kinematic.velocity);
+                kinematic.path = path;
+                kinematic.target = target.unwrap_or(0);
+                kinematic.backwards = backwards.unwrap_or(false);
+                Ok(kinematic)
+            },
+            _ => Err(problems)
+        }
+    }
+    /// Sets delta to the distance moved this tick
+    pub fn start_tick(&mut self, td: f32) {
+        self.po.delta = match self.path {
+            Some(path) if !path.points().is_empty() => self.path_step(path, path.speed * td),
+            _ => scale(self.velocity, td)
+        };
+    }
+    /// Velocity this tick, path included, per td like the delta of a dynamic
+    pub fn tick_velocity(&self, td: f32) -> [f32;2] {
+        if td > 0.0 { scale(self.po.delta, 1.0 / td) } else { [0.0, 0.0] }
+    }
+    /// Moves to the end of the tick, returns how far it moved
+    pub fn finish_tick(&mut self) -> [f32;2] {
+        let step = self.po.delta;
+        self.po.pos = add(self.po.pos, step);
+        self.po.delta = [0.0, 0.0];
+        step
+    }
+    fn path_step(&mut self, path: KinematicPath, mut distance: f32) -> [f32;2] {
+        let points = path.points();
+        let mut at = self.po.pos;
+
+        // at most one lap, so a zero length path can't loop forever.
+        // Going back and forth passes every point but the ends twice
+        let lap = if path.looping { points.len() } else { (2*points.len()).saturating_sub(2).max(1) };
+        for _ in 0..lap {
+            let target = points[self.target.min(points.len() - 1)];
+            let remaining = length(sub(target, at));
+            if remaining > distance {
+                at = add(at, scale(sub(target, at), distance / remaining));
+                break;
+            }
+            at = target;
+            distance -= remaining;
+            self.next_target(&path);
+        }
+
+        sub(at, self.po.pos)
+    }
+    fn next_target(&mut self, path: &KinematicPath) {
+        let last = path.points().len() - 1;
+        if path.looping {
+            self.target = if self.target >= last { 0 } else { self.target + 1 };
+            return;
+        }
+        if last == 0 {
+            return;
+        }
+        if self.backwards && self.target == 0 || !self.backwards && self.target >= last {
+            self.backwards = !self.backwards;
+        }
+        self.target = if self.backwards { self.target - 1 } else { self.target + 1 };
+    }
+}
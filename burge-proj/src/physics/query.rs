@@ -0,0 +1,288 @@
+use uuid::Uuid;
+
+use super::shape::{add, cross, dot, length, normalize, penetration, scale, sub, Core};
+use super::{box_collider, Collider, POMComponent, PhysObj, World};
+
+
+/// Closest object found by a raycast or shape cast
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub uuid: Uuid,
+    pub point: [f32;2],
+    pub normal: [f32;2],
+    pub distance: f32
+}
+
+/// Which objects a query can return
+#[derive(Clone, Copy, Debug)]
+pub struct QueryFilter {
+    /// Skipped, usually the object making the query
+    pub exclude: Option<Uuid>,
+    /// Skip objects that aren't solid
+    pub solid_only: bool,
+    /// Only objects on one of these layers
+    pub mask: u32
+}
+
+impl std::default::Default for QueryFilter {
+    fn default() -> Self {
+        Self {
+            exclude: None,
+            solid_only: false,
+            mask: u32::MAX
+        }
+    }
+}
+
+impl QueryFilter {
+    pub fn accepts(&self, uuid: &Uuid, po: &PhysObj) -> bool {
+        self.exclude != Some(*uuid) && (!self.solid_only || po.settings.solid) && po.settings.layer & self.mask != 0
+    }
+}
+
+
+impl POMComponent {
+    /// First object hit travelling from `origin` along `direction`, None if `max_distance` isn't finite
+    pub fn raycast(&self, origin: [f32;2], direction: [f32;2], max_distance: f32, filter: QueryFilter) -> Option<RayHit> {
+        if !max_distance.is_finite() || max_distance < 0.0 {
+            return None;
+        }
+        let direction = normalize(direction)?;
+        let end = add(origin, scale(direction, max_distance));
+        let bounds = ([origin[0].min(end[0]), origin[1].min(end[1])], [origin[0].max(end[0]), origin[1].max(end[1])]);
+
+        let mut closest: Option<RayHit> = None;
+        self.world.borrow().candidates(bounds, filter, |uuid, po| {
+            if let Some((distance, normal)) = ray_core(&po.core(), origin, direction, max_distance) {
+                if closest.is_none_or(|hit| distance < hit.distance) {
+                    closest = Some(RayHit {
+                        uuid: *uuid,
+                        point: add(origin, scale(direction, distance)),
+                        normal: normal,
+                        distance: distance
+                    });
+                }
+            }
+        });
+        closest
+    }
+    /// Every object touching the box from `min` to `max`
+    pub fn overlap_aabb(&self, min: [f32;2], max: [f32;2], filter: QueryFilter) -> Vec<Uuid> {
+        let area = box_collider(min, sub(max, min));
+
+        let mut found = Vec::new();
+        self.world.borrow().candidates((min, max), filter, |uuid, po| {
+            if area.intersects(po) {
+                found.push(*uuid);
+            }
+        });
+        found
+    }
+    /// First object hit moving `shape` by `translation`
+    pub fn shape_cast(&self, shape: &PhysObj, translation: [f32;2], filter: QueryFilter) -> Option<RayHit> {
+        let distance = length(translation);
+        let (start_min, start_max) = shape.bounds();
+        let bounds = (
+            [start_min[0] + translation[0].min(0.0), start_min[1] + translation[1].min(0.0)],
+            [start_max[0] + translation[0].max(0.0), start_max[1] + translation[1].max(0.0)]
+        );
+
+        let mut closest: Option<RayHit> = None;
+        self.world.borrow().candidates(bounds, filter, |uuid, po| {
+            if let Some((t, point, normal)) = time_of_impact(shape, translation, po) {
+                if closest.is_none_or(|hit| t*distance < hit.distance) {
+                    closest = Some(RayHit {
+                        uuid: *uuid,
+                        point: point,
+                        normal: normal,
+                        distance: t*distance
+                    });
+                }
+            }
+        });
+        closest
+    }
+}
+
+
+impl World {
+    /// Calls `f` for every static, kinematic and last tick dynamic that could touch `bounds`
+    fn candidates(&self, bounds: ([f32;2],[f32;2]), filter: QueryFilter, mut f: impl FnMut(&Uuid, &PhysObj)) {
+        for uuid in self.nearby_statics(bounds) {
+            let po = &self.static_po(&uuid);
+            if filter.accepts(&uuid, po) {
+                f(&uuid, po);
+            }
+        }
+        for (uuid, kinematic) in &self.kinematics {
+            if filter.accepts(uuid, &kinematic.po) {
+                f(uuid, &kinematic.po);
+            }
+        }
+        for (uuid, po) in &self.dynamics {
+            let (min, max) = po.bounds();
+            let (q_min, q_max) = bounds;
+            if min[0] <= q_max[0] && max[0] >= q_min[0] && min[1] <= q_max[1] && max[1] >= q_min[1] && filter.accepts(uuid, po) {
+                f(uuid, po);
+            }
+        }
+    }
+}
+
+
+/// Fraction of `translation` `this` can move before touching `other`,
+/// with the contact point and the normal of `other` at that point
+pub(super) fn time_of_impact(this: &PhysObj, translation: [f32;2], other: &PhysObj) -> Option<(f32, [f32;2], [f32;2])> {
+    let moved = |t: f32| PhysObj {
+        pos: add(this.pos, scale(translation, t)),
+        ..*this
+    };
+
+    let other_core = other.core();
+    let contact = |t: f32, normal: [f32;2]| {
+        let point = other_core.closest_point(moved(t).core().centre());
+        add(point, scale(normal, other_core.radius))
+    };
+
+    if this.intersects(other) {
+        let normal = normalize(this.exclusive_delta(other)).unwrap_or([0.0, 1.0]);
+        return Some((0.0, contact(0.0, normal), normal));
+    }
+    let distance = length(translation);
+    if distance == 0.0 {
+        return None;
+    }
+
+    if let (Collider::Aabb, Collider::Aabb) = (this.collider, other.collider) {
+        // swept box: cast the bottom left corner of this against other grown by the size of this
+        let grown = box_collider(sub(add(other.pos, other.delta), this.shape), add(other.shape, this.shape));
+        let (d, normal) = ray_core(&grown.core(), add(this.pos, this.delta), scale(translation, 1.0/distance), distance)?;
+        return Some((d/distance, contact(d/distance, normal), normal));
+    }
+
+    // step no further than half the smallest extent so thin objects aren't skipped
+    let (min, max) = this.bounds();
+    let extent = (max[0] - min[0]).min(max[1] - min[1]).max(0.01);
+    let steps = ((length(translation) / (extent*0.5)).ceil() as usize).clamp(1, 1000);
+
+    let mut before = 0.0;
+    for i in 1..=steps {
+        let t = i as f32 / steps as f32;
+        if penetration(&moved(t).core(), &other_core).is_none() {
+            before = t;
+            continue;
+        }
+
+        let mut after = t;
+        for _ in 0..16 {
+            let mid = (before + after) / 2.0;
+            if penetration(&moved(mid).core(), &other_core).is_some() {
+                after = mid;
+            } else {
+                before = mid;
+            }
+        }
+
+        let normal = penetration(&moved(after).core(), &other_core).and_then(normalize).unwrap_or([0.0, 1.0]);
+        return Some((before, contact(before, normal), normal));
+    }
+
+    None
+}
+
+
+/// Distance along a ray to a collider, with the surface normal at the hit
+pub(super) fn ray_core(core: &Core, origin: [f32;2], direction: [f32;2], max_distance: f32) -> Option<(f32, [f32;2])> {
+    let verts = core.verts();
+    if core.radius <= 0.0 {
+        return ray_convex(verts, origin, direction, max_distance);
+    }
+
+    // rounded: circles on every vertex, and each edge grown into a box
+    let mut closest = None;
+    let mut consider = |hit: Option<(f32, [f32;2])>| {
+        if let Some((d, n)) = hit {
+            if closest.is_none_or(|(best, _)| d < best) {
+                closest = Some((d, n));
+            }
+        }
+    };
+    if verts.len() > 2 {
+        consider(ray_convex(verts, origin, direction, max_distance));
+    }
+    for v in verts {
+        consider(ray_circle(*v, core.radius, origin, direction, max_distance));
+    }
+    let edges = if verts.len() == 2 { 1 } else if verts.len() > 2 { verts.len() } else { 0 };
+    for i in 0..edges {
+        let (a, b) = (verts[i], verts[(i+1)%verts.len()]);
+        if let Some(n) = normalize([b[1]-a[1], a[0]-b[0]]) {
+            let n = scale(n, core.radius);
+            consider(ray_convex(&[sub(a, n), sub(b, n), add(b, n), add(a, n)], origin, direction, max_distance));
+        }
+    }
+    closest
+}
+
+fn ray_circle(centre: [f32;2], radius: f32, origin: [f32;2], direction: [f32;2], max_distance: f32) -> Option<(f32, [f32;2])> {
+    let offset = sub(origin, centre);
+    let c = dot(offset, offset) - radius*radius;
+    if c <= 0.0 {
+        return Some((0.0, scale(direction, -1.0)));
+    }
+    let b = dot(offset, direction);
+    let discriminant = b*b - c;
+    if b > 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let d = -b - discriminant.sqrt();
+    if d > max_distance {
+        return None;
+    }
+    let normal = normalize(sub(add(origin, scale(direction, d)), centre)).unwrap_or([0.0, 1.0]);
+    Some((d, normal))
+}
+
+/// Ray against a convex polygon of either winding
+fn ray_convex(verts: &[[f32;2]], origin: [f32;2], direction: [f32;2], max_distance: f32) -> Option<(f32, [f32;2])> {
+    if verts.len() < 3 {
+        return None;
+    }
+    let mut area = 0.0;
+    for i in 0..verts.len() {
+        area += cross(verts[i], verts[(i+1)%verts.len()]);
+    }
+    let winding = if area < 0.0 { -1.0 } else { 1.0 };
+
+    let mut enter = 0.0;
+    let mut exit = max_distance;
+    let mut normal = None;
+    for i in 0..verts.len() {
+        let (a, b) = (verts[i], verts[(i+1)%verts.len()]);
+        let n = match normalize(scale([b[1]-a[1], a[0]-b[0]], winding)) {
+            Some(n) => n,
+            None => continue
+        };
+        let outside = dot(n, sub(origin, a));
+        let approach = dot(n, direction);
+        if approach == 0.0 {
+            if outside > 0.0 { return None; }
+            continue;
+        }
+        let d = -outside / approach;
+        if approach < 0.0 {
+            if d > enter {
+                enter = d;
+                normal = Some(n);
+            }
+        } else if d < exit {
+            exit = d;
+        }
+        if enter > exit {
+            return None;
+        }
+    }
+
+    // a ray starting inside hits straight away
+    Some((enter, normal.unwrap_or(scale(direction, -1.0))))
+}
@@ -0,0 +1,351 @@
+use crate::scene::{f32_field, SceneProblem};
+
+pub const MAX_POLYGON_VERTS: usize = 8;
+
+
+/// Shape of a PhysObj, positioned relative to `PhysObj::pos`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Collider {
+    /// Box of size `PhysObj::shape`, pos is its bottom left
+    Aabb,
+    /// Circle filling the box [2*radius, 2*radius] from pos
+    Circle { radius: f32 },
+    /// Upright capsule filling the box [2*radius, height] from pos
+    Capsule { radius: f32, height: f32 },
+    /// Convex polygon, vertices are offsets from pos
+    Polygon(ConvexPolygon)
+}
+
+impl Collider {
+    pub fn save(&self) -> serde_json::Value {
+        match self {
+            Collider::Aabb => serde_json::json!({ "kind": "aabb" }),
+            Collider::Circle { radius } => serde_json::json!({ "kind": "circle", "radius": radius }),
+            Collider::Capsule { radius, height } => serde_json::json!({ "kind": "capsule", "radius": radius, "height": height }),
+            Collider::Polygon(polygon) => serde_json::json!({ "kind": "polygon", "verts": polygon.verts() })
+        }
+    }
+    /// Collider in the format written by save
+    pub fn load(data: &serde_json::Map<String, serde_json::Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`.
+    /// Polygons with fewer than 3 or too many vertices, or that aren't convex, are problems
+    pub fn try_load(data: &serde_json::Map<String, serde_json::Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let number = |key: &str, problems: &mut Vec<SceneProblem>| {
+            if !data.contains_key(key) {
+                problems.push(SceneProblem::new(key, "missing"));
+            }
+            f32_field(data, key, problems)
+        };
+
+        let collider = match data.get("kind").and_then(|v| v.as_str()) {
+            Some("aabb") => Some(Collider::Aabb),
+            Some("circle") => number("radius", &mut problems).map(|radius| Collider::Circle { radius: radius }),
+            Some("capsule") => match (number("radius", &mut problems), number("height", &mut problems)) {
+                (Some(radius), Some(height)) => Some(Collider::Capsule { radius: radius, height: height }),
+                _ => None
+            },
+            Some("polygon") => match data.get("verts").and_then(|v| v.as_array()) {
+                Some(values) => {
+                    let mut verts = Vec::new();
+                    for (i, v) in values.iter().enumerate() {
+                        match crate::try_vec2!(v) {
+                            Some(v) => verts.push(v),
+                            None => problems.push(SceneProblem::new(format!("verts[{}]", i), "expected [x, y]"))
+                        }
+                    }
+                    if verts.len() < 3 {
+                        problems.push(SceneProblem::new("verts", "at least 3 vertices"));
+                    } else if verts.len() > MAX_POLYGON_VERTS {
+                        problems.push(SceneProblem::new("verts", format!("at most {} vertices", MAX_POLYGON_VERTS)));
+                    } else if !ConvexPolygon::is_convex(&verts) {
+                        problems.push(SceneProblem::new("verts", "not convex"));
+                    }
+                    Some(Collider::Polygon(ConvexPolygon::new(&verts)))
+                },
+                None => {
+                    problems.push(SceneProblem::new("verts", "expected an array of [x, y]"));
+                    None
+                }
+            },
+            Some(kind) => {
+                problems.push(SceneProblem::new("kind", format!("unknown collider kind \"{}\"", kind)));
+                None
+            },
+            None => {
+                problems.push(SceneProblem::new("kind", "expected a string"));
+                None
+            }
+        };
+
+        match collider {
+            Some(collider) if problems.is_empty() => Ok(collider),
+            _ => Err(problems)
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConvexPolygon {
+    verts: [[f32;2]; MAX_POLYGON_VERTS],
+    len: usize
+}
+
+impl ConvexPolygon {
+    /// Vertices past MAX_POLYGON_VERTS are dropped and convexity isn't checked,
+    /// see try_new. Clockwise winding is reversed
+    pub fn new(verts: &[[f32;2]]) -> Self {
+        let len = verts.len().min(MAX_POLYGON_VERTS);
+        let mut polygon = Self {
+            verts: [[0.0,0.0]; MAX_POLYGON_VERTS],
+            len: len
+        };
+        polygon.verts[..len].copy_from_slice(&verts[..len]);
+
+        let mut area = 0.0;
+        for i in 0..len {
+            let (a, b) = (polygon.verts[i], polygon.verts[(i+1)%len]);
+            area += a[0]*b[1] - b[0]*a[1];
+        }
+        if area < 0.0 {
+            polygon.verts[..len].reverse();
+        }
+
+        polygon
+    }
+    /// Like new, but None if there are fewer than 3 or too many vertices, or they aren't convex
+    pub fn try_new(verts: &[[f32;2]]) -> Option<Self> {
+        if verts.len() > MAX_POLYGON_VERTS || !Self::is_convex(verts) {
+            return None;
+        }
+        Some(Self::new(verts))
+    }
+    /// Whether `verts` turn the same way at every corner and go round once, either winding.
+    /// False for fewer than 3 vertices or none with area
+    pub fn is_convex(verts: &[[f32;2]]) -> bool {
+        let len = verts.len();
+        if len < 3 {
+            return false;
+        }
+        let mut sign = 0.0;
+        let mut turned = 0.0;
+        for i in 0..len {
+            let a = sub(verts[(i+1)%len], verts[i]);
+            let b = sub(verts[(i+2)%len], verts[(i+1)%len]);
+            let turn = cross(a, b);
+            if turn != 0.0 {
+                if sign * turn < 0.0 {
+                    return false;
+                }
+                sign = turn;
+            }
+            turned += turn.atan2(dot(a, b));
+        }
+        // points on a line never turn, and stars turn the same way at every corner
+        // but go round more than once
+        sign != 0.0 && (turned.abs() - std::f32::consts::TAU).abs() < 1e-3
+    }
+    pub fn verts(&self) -> &[[f32;2]] {
+        &self.verts[..self.len]
+    }
+}
+
+
+/// World space form of a collider: a convex hull of up to MAX_POLYGON_VERTS
+/// points, grown by `radius` (one point for circles, two for capsules)
+#[derive(Clone, Copy, Debug)]
+pub struct Core {
+    verts: [[f32;2]; MAX_POLYGON_VERTS],
+    len: usize,
+    pub radius: f32
+}
+
+impl Core {
+    pub fn new(collider: &Collider, pos: [f32;2], shape: [f32;2]) -> Self {
+        let mut core = Self {
+            verts: [[0.0,0.0]; MAX_POLYGON_VERTS],
+            len: 0,
+            radius: 0.0
+        };
+        let [x, y] = pos;
+        match collider {
+            Collider::Aabb => {
+                core.set(&[[x,y], [x+shape[0],y], [x+shape[0],y+shape[1]], [x,y+shape[1]]]);
+            },
+            Collider::Circle { radius } => {
+                core.set(&[[x+radius, y+radius]]);
+                core.radius = *radius;
+            },
+            Collider::Capsule { radius, height } => {
+                if *height > 2.0*radius {
+                    core.set(&[[x+radius, y+radius], [x+radius, y+height-radius]]);
+                } else {
+                    core.set(&[[x+radius, y+radius]]);
+                }
+                core.radius = *radius;
+            },
+            Collider::Polygon(polygon) => {
+                core.len = polygon.verts().len();
+                for (v, offset) in core.verts.iter_mut().zip(polygon.verts()) {
+                    *v = add(*offset, pos);
+                }
+            }
+        }
+        core
+    }
+    fn set(&mut self, verts: &[[f32;2]]) {
+        self.len = verts.len();
+        self.verts[..self.len].copy_from_slice(verts);
+    }
+    pub fn verts(&self) -> &[[f32;2]] {
+        &self.verts[..self.len]
+    }
+    pub fn centre(&self) -> [f32;2] {
+        let mut sum = [0.0, 0.0];
+        for v in self.verts() {
+            sum = add(sum, *v);
+        }
+        scale(sum, 1.0 / self.len.max(1) as f32)
+    }
+    pub fn bounds(&self) -> ([f32;2],[f32;2]) {
+        let mut min = [f32::INFINITY, f32::INFINITY];
+        let mut max = [f32::NEG_INFINITY, f32::NEG_INFINITY];
+        for v in self.verts() {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(v[axis] - self.radius);
+                max[axis] = max[axis].max(v[axis] + self.radius);
+            }
+        }
+        (min, max)
+    }
+    /// Outward normals of each edge, a segment has one normal
+    pub fn normals(&self) -> Vec<[f32;2]> {
+        let verts = self.verts();
+        let edges = match verts.len() {
+            0 | 1 => 0,
+            2 => 1,
+            n => n
+        };
+        (0..edges).filter_map(|i| {
+            let e = sub(verts[(i+1)%verts.len()], verts[i]);
+            normalize([e[1], -e[0]])
+        }).collect()
+    }
+    /// Closest point of the hull (ignoring radius) to `p`
+    pub fn closest_point(&self, p: [f32;2]) -> [f32;2] {
+        let verts = self.verts();
+        if verts.len() == 1 {
+            return verts[0];
+        }
+        if verts.len() > 2 && (0..verts.len()).all(|i| cross(sub(verts[(i+1)%verts.len()], verts[i]), sub(p, verts[i])) >= 0.0) {
+            return p;
+        }
+
+        let mut best = verts[0];
+        let mut best_dist = f32::INFINITY;
+        for i in 0..verts.len() {
+            let point = closest_on_segment(verts[i], verts[(i+1)%verts.len()], p);
+            let dist = length(sub(p, point));
+            if dist < best_dist {
+                best = point;
+                best_dist = dist;
+            }
+        }
+        best
+    }
+    fn project(&self, axis: [f32;2]) -> (f32, f32) {
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        for v in self.verts() {
+            let d = dot(*v, axis);
+            min = min.min(d);
+            max = max.max(d);
+        }
+        (min - self.radius, max + self.radius)
+    }
+}
+
+
+/// Smallest translation that moves `a` out of `b`, None if they don't touch
+pub fn penetration(a: &Core, b: &Core) -> Option<[f32;2]> {
+    let mut axes = a.normals();
+    axes.append(&mut b.normals());
+
+    // rounded shapes can also be separated along the line between their closest features
+    if a.radius > 0.0 || b.radius > 0.0 {
+        for v in a.verts() {
+            if let Some(axis) = normalize(sub(*v, b.closest_point(*v))) {
+                axes.push(axis);
+            }
+        }
+        for v in b.verts() {
+            if let Some(axis) = normalize(sub(a.closest_point(*v), *v)) {
+                axes.push(axis);
+            }
+        }
+    }
+    if axes.is_empty() {
+        axes.push([0.0, 1.0]);
+    }
+
+    let mut best: Option<(f32, [f32;2])> = None;
+    for axis in axes {
+        let (a_min, a_max) = a.project(axis);
+        let (b_min, b_max) = b.project(axis);
+        if a_max < b_min || b_max < a_min {
+            return None;
+        }
+
+        let (depth, direction) = if b_max - a_min < a_max - b_min {
+            (b_max - a_min, axis)
+        } else {
+            (a_max - b_min, scale(axis, -1.0))
+        };
+        if best.is_none_or(|(best_depth, _)| depth < best_depth) {
+            best = Some((depth, direction));
+        }
+    }
+
+    best.map(|(depth, direction)| scale(direction, depth))
+}
+
+
+pub(crate) fn closest_on_segment(a: [f32;2], b: [f32;2], p: [f32;2]) -> [f32;2] {
+    let ab = sub(b, a);
+    let len_sq = dot(ab, ab);
+    if len_sq == 0.0 {
+        return a;
+    }
+    let t = (dot(sub(p, a), ab) / len_sq).clamp(0.0, 1.0);
+    add(a, scale(ab, t))
+}
+
+pub(crate) fn add(a: [f32;2], b: [f32;2]) -> [f32;2] {
+    [a[0]+b[0], a[1]+b[1]]
+}
+pub(crate) fn sub(a: [f32;2], b: [f32;2]) -> [f32;2] {
+    [a[0]-b[0], a[1]-b[1]]
+}
+pub(crate) fn scale(a: [f32;2], s: f32) -> [f32;2] {
+    [a[0]*s, a[1]*s]
+}
+pub(crate) fn dot(a: [f32;2], b: [f32;2]) -> f32 {
+    a[0]*b[0] + a[1]*b[1]
+}
+pub(crate) fn cross(a: [f32;2], b: [f32;2]) -> f32 {
+    a[0]*b[1] - a[1]*b[0]
+}
+pub(crate) fn length(a: [f32;2]) -> f32 {
+    dot(a, a).sqrt()
+}
+pub(crate) fn normalize(a: [f32;2]) -> Option<[f32;2]> {
+    let len = length(a);
+    if len > f32::EPSILON {
+        Some(scale(a, 1.0/len))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,359 @@
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::event::RoutedEvent;
+use crate::scene::{object_field, uuid_field, vec2_field, SceneProblem};
+use super::kinematic::Kinematic;
+use super::{ContactSummary, ForceField, Joint, KinematicPath, POMComponent, PhysEvent, PhysObj, Side, World};
+
+
+/// Full state of a PhysObjManager, for rollback and rewinding.
+/// Callbacks survive restoring a snapshot but not saving it to JSON
+#[derive(Clone)]
+pub struct PhysSnapshot {
+    statics: Vec<(Uuid, PhysObj)>,
+    dynamics: Vec<(Uuid, PhysObj)>,
+    kinematics: Vec<(Uuid, Kinematic)>,
+    joints: Vec<(Uuid, Joint)>,
+    gravity: [f32;2],
+    fields: Vec<(Uuid, ForceField)>,
+    triggered: Vec<(Uuid, Uuid)>,
+    contacts: Vec<([f32;2],[f32;2])>,
+    state_hash: u64,
+    in_flight: Vec<(Uuid, [f32;2], [f32;2])>,
+    /// Sent to the POM and not handled yet
+    incoming: Vec<PhysEvent>,
+    /// Sent by the POM and not routed yet
+    outgoing: Vec<RoutedEvent<PhysEvent>>
+}
+
+impl PhysSnapshot {
+    /// POMComponent::state_hash when the snapshot was taken
+    pub fn state_hash(&self) -> u64 {
+        self.state_hash
+    }
+    pub fn save(&self) -> Value {
+        let objects = |objects: &Vec<(Uuid, PhysObj)>| -> Vec<Value> {
+            objects.iter().map(|(uuid, po)| json!({ "uuid": uuid.to_string(), "po": po.save() })).collect()
+        };
+        json!({
+            "statics": objects(&self.statics),
+            "dynamics": objects(&self.dynamics),
+            "kinematics": self.kinematics.iter().map(|(uuid, k)| json!({ "uuid": uuid.to_string(), "kinematic": k.save() })).collect::<Vec<Value>>(),
+            "joints": self.joints.iter().map(|(uuid, joint)| {
+                let mut data = joint.save();
+                data["uuid"] = uuid.to_string().into();
+                data
+            }).collect::<Vec<Value>>(),
+            "gravity": self.gravity,
+            "fields": self.fields.iter().map(|(uuid, field)| {
+                let mut data = field.save();
+                data["uuid"] = uuid.to_string().into();
+                data
+            }).collect::<Vec<Value>>(),
+            "triggered": self.triggered.iter().map(|(a, b)| json!([a.to_string(), b.to_string()])).collect::<Vec<Value>>(),
+            "contacts": self.contacts,
+            "state_hash": self.state_hash,
+            "in_flight": self.in_flight.iter().map(|(uuid, push, change)| json!({ "uuid": uuid.to_string(), "push": push, "change": change })).collect::<Vec<Value>>(),
+            "incoming": self.incoming.iter().map(|e| e.save()).collect::<Vec<Value>>(),
+            "outgoing": self.outgoing.iter().map(|RoutedEvent(uuid, e)| json!({
+                "to": uuid.map(|uuid| uuid.to_string()),
+                "event": e.save()
+            })).collect::<Vec<Value>>()
+        })
+    }
+    /// Snapshot in the format written by save, None if any part is invalid
+    pub fn load(data: &Map<String, Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &Map<String, Value>) -> Result<Self, Vec<SceneProblem>> {
+        let mut problems = Vec::new();
+        let objects = |key: &str, problems: &mut Vec<SceneProblem>| entries(data, key, problems, |o, problems| {
+            required(o, "uuid", problems, uuid_field).zip(nested(o, "po", problems, PhysObj::try_load))
+        });
+
+        let statics = objects("statics", &mut problems);
+        let dynamics = objects("dynamics", &mut problems);
+        let kinematics = entries(data, "kinematics", &mut problems, |k, problems| {
+            required(k, "uuid", problems, uuid_field).zip(nested(k, "kinematic", problems, Kinematic::try_load))
+        });
+        let joints = entries(data, "joints", &mut problems, |j, problems| {
+            required(j, "uuid", problems, uuid_field).zip(loaded(Joint::try_load(j), "", problems))
+        });
+        let gravity = vec2_field(data, "gravity", &mut problems);
+        let fields = entries(data, "fields", &mut problems, |f, problems| {
+            required(f, "uuid", problems, uuid_field).zip(loaded(ForceField::try_load(f), "", problems))
+        });
+        let mut triggered = Vec::new();
+        for (i, pair) in list(data, "triggered", &mut problems).iter().enumerate() {
+            let pair = pair.as_array().filter(|v| v.len() == 2).map(|v| (&v[0], &v[1]));
+            match pair.and_then(|(a, b)| crate::try_uuid!(a).zip(crate::try_uuid!(b))) {
+                Some(pair) => triggered.push(pair),
+                None => problems.push(SceneProblem::new(format!("triggered[{}]", i), "expected a pair of uuids"))
+            }
+        }
+        let mut contacts = Vec::new();
+        for (i, pair) in list(data, "contacts", &mut problems).iter().enumerate() {
+            let pair = pair.as_array().filter(|v| v.len() == 2).map(|v| (&v[0], &v[1]));
+            match pair.and_then(|(a, b)| crate::try_vec2!(a).zip(crate::try_vec2!(b))) {
+                Some(pair) => contacts.push(pair),
+                None => problems.push(SceneProblem::new(format!("contacts[{}]", i), "expected a pair of [x, y]"))
+            }
+        }
+        let state_hash = match data.get("state_hash") {
+            Some(hash) => match hash.as_u64() {
+                Some(hash) => Some(hash),
+                None => {
+                    problems.push(SceneProblem::new("state_hash", "expected a hash"));
+                    None
+                }
+            },
+            None => None
+        };
+        let in_flight = entries(data, "in_flight", &mut problems, |f, problems| {
+            let uuid = required(f, "uuid", problems, uuid_field);
+            let push = required(f, "push", problems, vec2_field);
+            let change = required(f, "change", problems, vec2_field);
+            uuid.zip(push).zip(change).map(|((uuid, push), change)| (uuid, push, change))
+        });
+        let incoming = entries(data, "incoming", &mut problems, |e, problems| loaded(PhysEvent::try_load(e), "", problems));
+        let outgoing = entries(data, "outgoing", &mut problems, |e, problems| {
+            // null is sent to every receiver
+            let to = match e.get("to") {
+                Some(Value::Null) | None => Some(None),
+                Some(_) => uuid_field(e, "to", problems).map(Some)
+            };
+            to.zip(nested(e, "event", problems, PhysEvent::try_load)).map(|(to, event)| RoutedEvent(to, event))
+        });
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Self {
+            statics: statics,
+            dynamics: dynamics,
+            kinematics: kinematics,
+            joints: joints,
+            gravity: gravity.unwrap_or([0.0, super::GRAVITY]),
+            fields: fields,
+            triggered: triggered,
+            contacts: contacts,
+            state_hash: state_hash.unwrap_or(0),
+            in_flight: in_flight,
+            incoming: incoming,
+            outgoing: outgoing
+        })
+    }
+}
+
+
+impl POMComponent {
+    pub fn snapshot(&self) -> PhysSnapshot {
+        let world = self.world.borrow();
+
+        // receivers drain when polled, so put the events back
+        let incoming = self.receiver.poll();
+        for e in &incoming {
+            self.receiver.receive(*e);
+        }
+
+        let mut statics: Vec<(Uuid, PhysObj)> = world.statics.iter().map(|(uuid, po)| (*uuid, *po)).collect();
+        statics.sort_by_key(|(uuid, _)| *uuid);
+        let mut triggered: Vec<(Uuid, Uuid)> = world.triggered.iter().copied().collect();
+        triggered.sort();
+
+        PhysSnapshot {
+            statics: statics,
+            dynamics: world.dynamics.clone(),
+            kinematics: world.kinematics.iter().map(|(uuid, k)| (*uuid, *k)).collect(),
+            joints: world.joints.clone(),
+            gravity: world.gravity,
+            fields: world.fields.clone(),
+            triggered: triggered,
+            contacts: world.contacts.clone(),
+            state_hash: world.hash_state(),
+            in_flight: world.in_flight.iter().map(|(uuid, (push, change))| (*uuid, *push, *change)).collect(),
+            incoming: incoming,
+            outgoing: self.send_queue.borrow().clone()
+        }
+    }
+    /// Puts the POM back to exactly how it was when `snapshot` was taken
+    pub fn restore(&self, snapshot: &PhysSnapshot) {
+        self.world.borrow_mut().restore(snapshot);
+
+        self.receiver.poll();
+        for e in &snapshot.incoming {
+            self.receiver.receive(*e);
+        }
+        *self.send_queue.borrow_mut() = snapshot.outgoing.clone();
+    }
+}
+
+
+impl World {
+    fn restore(&mut self, snapshot: &PhysSnapshot) {
+        self.statics.clear();
+        if let Some(broadphase) = &mut self.broadphase {
+            broadphase.clear();
+        }
+        for (uuid, po) in &snapshot.statics {
+            self.add_static(*uuid, *po);
+        }
+        self.dynamics = snapshot.dynamics.clone();
+        self.kinematics = snapshot.kinematics.iter().copied().collect();
+        self.joints = snapshot.joints.clone();
+        self.gravity = snapshot.gravity;
+        self.fields = snapshot.fields.clone();
+        self.triggered = snapshot.triggered.iter().copied().collect();
+        self.contacts = snapshot.contacts.clone();
+        self.in_flight = snapshot.in_flight.iter().map(|(uuid, push, change)| (*uuid, (*push, *change))).collect();
+    }
+}
+
+
+impl PhysEvent {
+    /// Event as JSON, objects lose their callbacks
+    pub fn save(&self) -> Value {
+        match self {
+            PhysEvent::StaticPO(uuid, po) => json!({ "event": "static_po", "uuid": uuid.to_string(), "po": po.save() }),
+            PhysEvent::DynamicPO(uuid, po) => json!({ "event": "dynamic_po", "uuid": uuid.to_string(), "po": po.save() }),
+            PhysEvent::RemoveStaticPO(uuid) => json!({ "event": "remove_static_po", "uuid": uuid.to_string() }),
+            PhysEvent::MoveStaticPO(uuid, pos) => json!({ "event": "move_static_po", "uuid": uuid.to_string(), "pos": pos }),
+            PhysEvent::KinematicPO(uuid, po) => json!({ "event": "kinematic_po", "uuid": uuid.to_string(), "po": po.save() }),
+            PhysEvent::SetKinematicPath(uuid, path) => json!({ "event": "set_kinematic_path", "uuid": uuid.to_string(), "path": path.save() }),
+            PhysEvent::RemoveKinematicPO(uuid) => json!({ "event": "remove_kinematic_po", "uuid": uuid.to_string() }),
+            PhysEvent::AddJoint(uuid, joint) => json!({ "event": "add_joint", "uuid": uuid.to_string(), "joint": joint.save() }),
+            PhysEvent::RemoveJoint(uuid) => json!({ "event": "remove_joint", "uuid": uuid.to_string() }),
+            PhysEvent::AddField(uuid, field) => json!({ "event": "add_field", "uuid": uuid.to_string(), "field": field.save() }),
+            PhysEvent::RemoveField(uuid) => json!({ "event": "remove_field", "uuid": uuid.to_string() }),
+            PhysEvent::SetGravity(v) => json!({ "event": "set_gravity", "vec": v }),
+            PhysEvent::ApplyImpulse(uuid, v) => json!({ "event": "apply_impulse", "uuid": uuid.to_string(), "vec": v }),
+            PhysEvent::ApplyForce(uuid, v) => json!({ "event": "apply_force", "uuid": uuid.to_string(), "vec": v }),
+            PhysEvent::ModPos(v) => json!({ "event": "mod_pos", "vec": v }),
+            PhysEvent::ModVelocity(v) => json!({ "event": "mod_velocity", "vec": v }),
+            PhysEvent::Collision(uuid, po) => json!({ "event": "collision", "uuid": uuid.to_string(), "po": po.save() }),
+            PhysEvent::Blocked(uuid, side) => json!({ "event": "blocked", "uuid": uuid.to_string(), "side": side.name() }),
+            PhysEvent::Contacts(summary) => json!({ "event": "contacts", "summary": summary.save() }),
+            PhysEvent::TriggerEnter(uuid) => json!({ "event": "trigger_enter", "uuid": uuid.to_string() }),
+            PhysEvent::TriggerStay(uuid) => json!({ "event": "trigger_stay", "uuid": uuid.to_string() }),
+            PhysEvent::TriggerExit(uuid) => json!({ "event": "trigger_exit", "uuid": uuid.to_string() }),
+            PhysEvent::Null => json!({ "event": "null" })
+        }
+    }
+    /// Event in the format written by save
+    pub fn load(data: &Map<String, Value>) -> Option<Self> {
+        Self::try_load(data).ok()
+    }
+    /// Like load, but reports what's wrong with `data`
+    pub fn try_load(data: &Map<String, Value>) -> Result<Self, Vec<SceneProblem>> {
+        let id = |problems: &mut Vec<SceneProblem>| required(data, "uuid", problems, uuid_field);
+        let po = |problems: &mut Vec<SceneProblem>| nested(data, "po", problems, PhysObj::try_load);
+        let v = |key: &str, problems: &mut Vec<SceneProblem>| required(data, key, problems, vec2_field);
+
+        let name = match data.get("event") {
+            Some(Value::String(name)) => name.as_str(),
+            Some(_) => return Err(vec![SceneProblem::new("event", "expected a string")]),
+            None => return Err(vec![SceneProblem::new("event", "missing")])
+        };
+        let mut problems = Vec::new();
+        let p = &mut problems;
+        let event = match name {
+            "static_po" => id(p).zip(po(p)).map(|(uuid, po)| PhysEvent::StaticPO(uuid, po)),
+            "dynamic_po" => id(p).zip(po(p)).map(|(uuid, po)| PhysEvent::DynamicPO(uuid, po)),
+            "remove_static_po" => id(p).map(PhysEvent::RemoveStaticPO),
+            "move_static_po" => id(p).zip(v("pos", p)).map(|(uuid, pos)| PhysEvent::MoveStaticPO(uuid, pos)),
+            "kinematic_po" => id(p).zip(po(p)).map(|(uuid, po)| PhysEvent::KinematicPO(uuid, po)),
+            "set_kinematic_path" => id(p).zip(nested(data, "path", p, KinematicPath::try_load)).map(|(uuid, path)| PhysEvent::SetKinematicPath(uuid, path)),
+            "remove_kinematic_po" => id(p).map(PhysEvent::RemoveKinematicPO),
+            "add_joint" => id(p).zip(nested(data, "joint", p, Joint::try_load)).map(|(uuid, joint)| PhysEvent::AddJoint(uuid, joint)),
+            "remove_joint" => id(p).map(PhysEvent::RemoveJoint),
+            "add_field" => id(p).zip(nested(data, "field", p, ForceField::try_load)).map(|(uuid, field)| PhysEvent::AddField(uuid, field)),
+            "remove_field" => id(p).map(PhysEvent::RemoveField),
+            "set_gravity" => v("vec", p).map(PhysEvent::SetGravity),
+            "apply_impulse" => id(p).zip(v("vec", p)).map(|(uuid, v)| PhysEvent::ApplyImpulse(uuid, v)),
+            "apply_force" => id(p).zip(v("vec", p)).map(|(uuid, v)| PhysEvent::ApplyForce(uuid, v)),
+            "mod_pos" => v("vec", p).map(PhysEvent::ModPos),
+            "mod_velocity" => v("vec", p).map(PhysEvent::ModVelocity),
+            "collision" => id(p).zip(po(p)).map(|(uuid, po)| PhysEvent::Collision(uuid, po)),
+            "blocked" => {
+                let side = data.get("side").and_then(|v| v.as_str()).and_then(Side::from_name);
+                if side.is_none() {
+                    p.push(SceneProblem::new("side", "expected left, right, top or bottom"));
+                }
+                id(p).zip(side).map(|(uuid, side)| PhysEvent::Blocked(uuid, side))
+            },
+            "contacts" => nested(data, "summary", p, ContactSummary::try_load).map(PhysEvent::Contacts),
+            "trigger_enter" => id(p).map(PhysEvent::TriggerEnter),
+            "trigger_stay" => id(p).map(PhysEvent::TriggerStay),
+            "trigger_exit" => id(p).map(PhysEvent::TriggerExit),
+            "null" => Some(PhysEvent::Null),
+            _ => {
+                p.push(SceneProblem::new("event", format!("unknown event \"{}\"", name)));
+                None
+            }
+        };
+
+        match event {
+            Some(event) if problems.is_empty() => Ok(event),
+            _ => Err(problems)
+        }
+    }
+}
+
+
+/// One of the scene field helpers, like vec2_field
+type Field<'a, T> = fn(&'a Map<String, Value>, &str, &mut Vec<SceneProblem>) -> Option<T>;
+/// A try_load function
+type Loader<T> = fn(&Map<String, Value>) -> Result<T, Vec<SceneProblem>>;
+
+/// Required field read with `field`, "missing" if it isn't there
+fn required<'a, T>(data: &'a Map<String, Value>, key: &str, problems: &mut Vec<SceneProblem>, field: Field<'a, T>) -> Option<T> {
+    if !data.contains_key(key) {
+        problems.push(SceneProblem::new(key, "missing"));
+    }
+    field(data, key, problems)
+}
+
+/// Required object at `key` loaded with `load`, its problems within `key`
+fn nested<T>(data: &Map<String, Value>, key: &str, problems: &mut Vec<SceneProblem>, load: Loader<T>) -> Option<T> {
+    let value = required(data, key, problems, object_field)?;
+    loaded(load(value), key, problems)
+}
+
+/// The value, or None with its problems added within `path`
+fn loaded<T>(result: Result<T, Vec<SceneProblem>>, path: &str, problems: &mut Vec<SceneProblem>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(errors) => {
+            problems.extend(errors.into_iter().map(|p| p.within(path)));
+            None
+        }
+    }
+}
+
+/// Array at `key`, empty if it's missing
+fn list<'a>(data: &'a Map<String, Value>, key: &str, problems: &mut Vec<SceneProblem>) -> &'a [Value] {
+    match data.get(key) {
+        Some(Value::Array(values)) => values,
+        Some(_) => {
+            problems.push(SceneProblem::new(key, "expected an array"));
+            &[]
+        },
+        None => &[]
+    }
+}
+
+/// Every object in the array at `key` loaded with `load`, problems within "key[i]"
+fn entries<T>(data: &Map<String, Value>, key: &str, problems: &mut Vec<SceneProblem>, load: impl Fn(&Map<String, Value>, &mut Vec<SceneProblem>) -> Option<T>) -> Vec<T> {
+    let mut loaded = Vec::new();
+    for (i, value) in list(data, key, problems).iter().enumerate() {
+        let mut errors = Vec::new();
+        match value.as_object() {
+            Some(value) => loaded.extend(load(value, &mut errors)),
+            None => errors.push(SceneProblem::new("", "expected an object"))
+        }
+        problems.extend(errors.into_iter().map(|p| p.within(&format!("{}[{}]", key, i))));
+    }
+    loaded
+}
@@ -1,63 +1,198 @@
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::os::windows;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use glium::Texture2d;
 use uuid::Uuid;
 
+use crate::physics::POMComponent;
 use crate::sprite::SpriteSheet;
+use crate::event::{Receiver, Sender};
+use crate::instance::InputEvent;
 use crate::{component::ComponentManager, element::Element};
 
+/// Sent with the "scenes" component every scene has, handled after the update it's sent in
+#[derive(Clone)]
 pub enum SceneEvent {
     SetCamera(Uuid),
+    /// Replaces the current scene
+    Switch(String, Transition),
+    /// Makes a scene current on top of the one below it, which is kept for Pop
+    Push(String, Overlay, Transition),
+    /// Goes back to the scene below the current one
+    Pop(Transition)
+}
+
+/// How a scene on the stack treats the scenes below it
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Overlay {
+    /// Hides and freezes everything below
+    #[default]
+    Opaque,
+    /// Drawn over the scene below, which is frozen, e.g. a pause menu
+    Pause,
+    /// Drawn over the scene below, which keeps running, e.g. a HUD
+    Passthrough
+}
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    None,
+    /// Fades out to the background colour and back in over this many seconds
+    Fade(f32),
+    /// Blends from the old scene to the new one over this many seconds
+    Crossfade(f32)
+}
+
+impl Transition {
+    fn duration(&self) -> f32 {
+        match *self {
+            Transition::None => 0.0,
+            Transition::Fade(seconds) | Transition::Crossfade(seconds) => seconds.max(0.0)
+        }
+    }
+}
+
+/// Scene change that's being faded or crossfaded
+struct ActiveTransition {
+    transition: Transition,
+    elapsed: f32,
+    /// Applied halfway through a fade, None once it has been
+    change: Option<SceneEvent>,
+    /// Scenes that were visible when a crossfade started, empty for fades
+    from: Vec<String>
 }
 
 #[derive(Default)]
 pub struct Scene {
     camera_uuid: Uuid,
     pub elements: HashMap<Uuid, Element>,
-    pub component_manager: ComponentManager
+    /// Uuids of elements in the order they were added, elements are always updated in this order
+    order: Vec<Uuid>,
+    pub component_manager: ComponentManager,
+    /// JSON names of elements loaded by name, only these are saved
+    element_names: HashMap<Uuid, String>,
+    /// JSON name of each component, by the name it was added under
+    component_names: HashMap<&'static str, String>,
+    /// Sprite positions from before the last update, for interpolation
+    previous_positions: HashMap<Uuid, [f32;2]>
 }
 
 impl Scene {
+    /// Runs on_enter for every element
+    pub fn enter(&mut self) {
+        for uuid in &self.order {
+            if let Some(element) = self.elements.get_mut(uuid) {
+                element.on_enter();
+            }
+        }
+    }
+    /// Runs on_exit for every element
+    pub fn exit(&mut self) {
+        for uuid in &self.order {
+            if let Some(element) = self.elements.get_mut(uuid) {
+                element.on_exit();
+            }
+        }
+    }
     pub fn init_elements(&mut self) {
-        for (uuid, element) in &mut self.elements {
-            if matches!(element, Element::Camera(..)) {
-                self.camera_uuid = *uuid;
+        for uuid in &self.order {
+            if let Some(element) = self.elements.get_mut(uuid) {
+                if matches!(element, Element::Camera(..)) {
+                    self.camera_uuid = *uuid;
+                }
+                element.init(*uuid, &self.component_manager);
             }
-            element.init(*uuid, &self.component_manager);
         }
     }
     pub fn update_elements(&mut self, td: f32) {
-        for (_, element) in &mut self.elements {
-            element.local_update(td);
+        self.previous_positions.clear();
+        for uuid in &self.order {
+            if let Some(Element::Entity(entity)) = self.elements.get(uuid) {
+                self.previous_positions.insert(*uuid, entity.sprite().pos());
+            }
         }
-        for (_, element) in &mut self.elements {
-            element.post_update();
+
+        self.order.retain(|uuid| self.elements.contains_key(uuid));
+        for uuid in &self.order {
+            self.elements.get_mut(uuid).unwrap().local_update(td);
+        }
+        for uuid in &self.order {
+            self.elements.get_mut(uuid).unwrap().post_update();
         }
     }
+    /// Records the JSON name `component` was loaded from, so it can be saved
+    pub fn add_named_component(&mut self, json_name: &str, component: Box<dyn Component>) {
+        self.component_names.insert(component.name(), json_name.to_string());
+        self.add_component(component.name(), component);
+    }
+    /// Records the JSON name `element` was loaded from, so it can be saved
+    pub fn add_named_element(&mut self, json_name: &str, uuid: Uuid, element: Element) {
+        self.element_names.insert(uuid, json_name.to_string());
+        self.add_element_with_uuid(uuid, element);
+    }
     pub fn add_component(&mut self, name: &'static str, component: Box<dyn Component>) {
         self.add_element(component.build_element());
         self.component_manager.add(name, component);
     }
     pub fn add_element(&mut self, element: Element) {
-        let uuid = Uuid::new_v4();
-        self.elements.insert(uuid, element);
+        self.add_element_with_uuid(crate::ids::new_uuid(), element);
+    }
+    /// Replaces any element already using `uuid`
+    pub fn add_element_with_uuid(&mut self, uuid: Uuid, element: Element) {
+        if self.elements.insert(uuid, element).is_none() {
+            self.order.push(uuid);
+        }
+    }
+
+    /// Components and named elements in the format JSONManager::create_scene reads,
+    /// without the scene "name"
+    pub fn save(&self) -> serde_json::Value {
+        let mut components: Vec<(&&'static str, &String)> = self.component_names.iter().collect();
+        components.sort_by(|a, b| a.1.cmp(b.1));
+        let components: Vec<serde_json::Value> = components.into_iter()
+            .filter_map(|(name, json_name)| Some(named(json_name, self.component_manager.get(name)?.save())))
+            .collect();
+
+        let elements: Vec<serde_json::Value> = self.order.iter()
+            .filter_map(|uuid| {
+                let mut data = named(self.element_names.get(uuid)?, self.elements.get(uuid)?.save());
+                data["uuid"] = uuid.to_string().into();
+                Some(data)
+            })
+            .collect();
+
+        serde_json::json!({ "components": components, "elements": elements })
     }
 
 
-    pub fn display(&self, sprite_sheet: &SpriteSheet) -> Vec<crate::Vertex> {
+    /// Vertices for every entity, `alpha` is how far between the last two updates to draw them
+    pub fn display(&self, sprite_sheet: &SpriteSheet, alpha: f32) -> Vec<crate::Vertex> {
         let mut vertices = Vec::new();
-        for (_, element) in &self.elements {
-            match element {
-                Element::Entity(entity) => vertices.append(&mut &mut sprite_sheet.vertices(entity.sprite())),
-                _ => ()
+        for uuid in &self.order {
+            if let Some(Element::Entity(entity)) = self.elements.get(uuid) {
+                let mut sprite = entity.sprite();
+                if let Some(previous) = self.previous_positions.get(uuid) {
+                    sprite = sprite.interpolated(*previous, alpha);
+                }
+                vertices.append(&mut sprite_sheet.vertices(sprite))
             }
         }
         vertices
     }
 
+    /// Line vertices for the physics debug overlay, empty without a "pom" component
+    pub fn debug_display(&self) -> Vec<crate::DebugVertex> {
+        let mut vertices = Vec::new();
+        self.component_manager.access("pom", |pom: &POMComponent| {
+            for line in pom.debug_lines() {
+                vertices.push(crate::DebugVertex { pos: line.from, color: line.color });
+                vertices.push(crate::DebugVertex { pos: line.to, color: line.color });
+            }
+        });
+        vertices
+    }
 
     pub fn camera_projection(&self, window_size: [u32;2]) -> ([[f32;3];3], [f32;2]) {
         if let Some(Element::Camera(camera)) = self.elements.get(&self.camera_uuid) {
@@ -73,6 +208,16 @@ impl Scene {
 
 use crate::component::Component;
 
+/// `data` with "name" added, anything but an object is replaced
+fn named(name: &str, data: serde_json::Value) -> serde_json::Value {
+    let mut fields = match data {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new()
+    };
+    fields.insert("name".to_string(), name.into());
+    serde_json::Value::Object(fields)
+}
+
 #[derive(Default)]
 pub struct JSONManager {
     pub element_names: HashMap<String, Element>,
@@ -81,49 +226,266 @@ pub struct JSONManager {
 
 
 impl JSONManager {
-    pub fn create_scene(&self, data: serde_json::Value, mut default_components: Vec<serde_json::Value>, mut default_elements: Vec<serde_json::Value>) -> Scene {
-        macro_rules! load_object {
-            ($hashmap:ident, $c:tt) => {
-                if let Value::Object(fields) = $c {
-                    if let Some(Value::String(name)) = fields.get("name") {
-                        if let Some(default) = self.$hashmap.get(name) {
-                            Some(default.load(&fields))
-                        } else { println!("Obj with name: '{}' not found in JSON Manager", name); None }
-                    } else { None }
-                } else { None }
-            };
-        }
-
+    /// Scene from JSON, or every problem found in it. Defaults that aren't registered are skipped
+    pub fn create_scene(&self, data: serde_json::Value, default_components: Vec<serde_json::Value>, default_elements: Vec<serde_json::Value>) -> Result<Scene, SceneError> {
         use serde_json::Value;
         let mut scene = Scene::default();
-        if let Value::Object(fields) = data {
-
-            if let Some(Value::Array(components)) = fields.get("components") {
-                let mut both = components.clone();
-                both.append(&mut default_components);
-                for c in both {
-                    let component = load_object!(component_names,c);
-                    if let Some(component) = component {
-                        scene.add_component(component.name(), component);
-                    }
+        let mut problems = Vec::new();
+
+        let fields = match data {
+            Value::Object(fields) => fields,
+            _ => return Err(SceneError { problems: vec![SceneProblem::new("", "expected an object")] })
+        };
+
+        for (path, data, is_default) in entries("components", &fields, &default_components, &mut problems) {
+            if let Some((c_fields, name, default)) = lookup(&path, data, &self.component_names, is_default, &mut problems) {
+                match default.try_load(c_fields) {
+                    Ok(component) => scene.add_named_component(name, component),
+                    Err(errors) => problems.extend(errors.into_iter().map(|p| p.within(&path)))
                 }
             }
+        }
 
-            
-            if let Some(Value::Array(elements)) = fields.get("elements") {
-                let mut both = elements.clone();
-                both.append(&mut default_elements);
-                for e in both {
-                    let element = load_object!(element_names,e);
-                    if let Some(element) = element {
-                        scene.add_element(element);
-                    }
+        for (path, data, is_default) in entries("elements", &fields, &default_elements, &mut problems) {
+            if let Some((e_fields, name, default)) = lookup(&path, data, &self.element_names, is_default, &mut problems) {
+                // a fixed "uuid" lets other JSON refer to the element, e.g. physics joints
+                let uuid = match e_fields.get("uuid") {
+                    Some(uuid) => uuid.as_str().and_then(|u| Uuid::parse_str(u).ok()),
+                    None => Some(crate::ids::new_uuid())
+                };
+                if uuid.is_none() {
+                    problems.push(SceneProblem::new(format!("{}.uuid", path), "expected a uuid string"));
+                }
+                match (uuid, default.try_load_in(e_fields, &scene.component_manager)) {
+                    (Some(uuid), Ok(element)) => scene.add_named_element(name, uuid, element),
+                    (_, Err(errors)) => problems.extend(errors.into_iter().map(|p| p.within(&path))),
+                    _ => ()
                 }
             }
         }
-        
-        scene
+
+        if problems.is_empty() {
+            Ok(scene)
+        } else {
+            Err(SceneError { problems: problems })
+        }
+    }
+}
+
+
+/// Entries of the scene's `key` array with their paths, followed by the defaults it doesn't replace.
+/// The bool is true for defaults
+fn entries<'a>(key: &str, fields: &'a serde_json::Map<String, serde_json::Value>, defaults: &'a [serde_json::Value], problems: &mut Vec<SceneProblem>) -> Vec<(String, &'a serde_json::Value, bool)> {
+    let listed = match fields.get(key) {
+        Some(serde_json::Value::Array(listed)) => listed,
+        Some(_) => {
+            problems.push(SceneProblem::new(key, "expected an array"));
+            return Vec::new();
+        },
+        None => return Vec::new()
+    };
+
+    let mut entries: Vec<(String, &serde_json::Value, bool)> = listed.iter().enumerate()
+        .map(|(i, data)| (format!("{}[{}]", key, i), data, false))
+        .collect();
+    // listed ones replace the defaults of the same name, so a saved scene doesn't get a second default camera
+    for (i, data) in defaults.iter().enumerate() {
+        if !listed.iter().any(|l| l.get("name") == data.get("name")) {
+            entries.push((format!("default_{}[{}]", key, i), data, true));
+        }
+    }
+    entries
+}
+
+/// Fields, name and registered default of one entry, None once the problem is recorded
+fn lookup<'a, T>(path: &str, data: &'a serde_json::Value, registry: &'a HashMap<String, T>, is_default: bool, problems: &mut Vec<SceneProblem>) -> Option<(&'a serde_json::Map<String, serde_json::Value>, &'a String, &'a T)> {
+    let fields = match data.as_object() {
+        Some(fields) => fields,
+        None => {
+            problems.push(SceneProblem::new(path, "expected an object"));
+            return None;
+        }
+    };
+    let name = match fields.get("name") {
+        Some(serde_json::Value::String(name)) => name,
+        Some(_) => {
+            problems.push(SceneProblem::new(format!("{}.name", path), "expected a string"));
+            return None;
+        },
+        None => {
+            problems.push(SceneProblem::new(format!("{}.name", path), "missing"));
+            return None;
+        }
+    };
+    match registry.get(name) {
+        Some(default) => Some((fields, name, default)),
+        None if is_default => None,
+        None => {
+            problems.push(SceneProblem::new(format!("{}.name", path), format!("nothing registered as '{}'", name)));
+            None
+        }
+    }
+}
+
+
+/// Something wrong with one value of scene JSON
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneProblem {
+    /// Where the value is, like "elements[3].pos", empty for the whole scene
+    pub path: String,
+    pub message: String
+}
+
+impl SceneProblem {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into()
+        }
+    }
+    /// The same problem in a value at `parent`, so "pos" in "elements[3]" is "elements[3].pos"
+    pub fn within(self, parent: &str) -> Self {
+        let path = if self.path.is_empty() {
+            parent.to_string()
+        } else if self.path.starts_with('[') || parent.is_empty() {
+            format!("{}{}", parent, self.path)
+        } else {
+            format!("{}.{}", parent, self.path)
+        };
+        Self { path: path, ..self }
+    }
+}
+
+impl std::fmt::Display for SceneProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Every problem found loading a scene
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneError {
+    pub problems: Vec<SceneProblem>
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let lines: Vec<String> = self.problems.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+
+/// A scene file that couldn't be added
+#[derive(Debug)]
+pub struct SceneFileError {
+    pub path: PathBuf,
+    pub kind: SceneFileErrorKind
+}
+
+#[derive(Debug)]
+pub enum SceneFileErrorKind {
+    Read(std::io::Error),
+    Json(serde_json::Error),
+    Scene(SceneError)
+}
+
+impl std::fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            SceneFileErrorKind::Read(e) => write!(f, "Couldn't read scene '{}': {}", self.path.display(), e),
+            SceneFileErrorKind::Json(e) => write!(f, "Invalid JSON in '{}': {}", self.path.display(), e),
+            SceneFileErrorKind::Scene(e) => write!(f, "Problems in scene '{}':\n{}", self.path.display(), e)
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            SceneFileErrorKind::Read(e) => Some(e),
+            SceneFileErrorKind::Json(e) => Some(e),
+            SceneFileErrorKind::Scene(e) => Some(e)
+        }
+    }
+}
+
+
+/// A SceneEvent that couldn't be handled
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneChangeError {
+    /// Switched or pushed to a scene that was never added
+    UnknownScene(String),
+    /// Switched or pushed to a scene that's already on the stack
+    AlreadyOnStack(String),
+    /// Popped the last scene on the stack
+    NothingToPop
+}
+
+impl std::fmt::Display for SceneChangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SceneChangeError::UnknownScene(name) => write!(f, "No scene called '{}' to change to", name),
+            SceneChangeError::AlreadyOnStack(name) => write!(f, "Scene '{}' is already on the stack", name),
+            SceneChangeError::NothingToPop => write!(f, "No scene to pop back to")
+        }
+    }
+}
+
+impl std::error::Error for SceneChangeError {}
+
+
+/// Optional [x, y] field, a problem if it's there but isn't two numbers
+pub fn vec2_field(data: &serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Option<[f32;2]> {
+    let value = data.get(key)?;
+    let vec2 = crate::try_vec2!(value);
+    if vec2.is_none() {
+        problems.push(SceneProblem::new(key, "expected [x, y]"));
     }
+    vec2
+}
+
+/// Optional number field, a problem if it's there but isn't a number
+pub fn f32_field(data: &serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Option<f32> {
+    let value = data.get(key)?;
+    if value.as_f64().is_none() {
+        problems.push(SceneProblem::new(key, "expected a number"));
+    }
+    value.as_f64().map(|v| v as f32)
+}
+
+/// Optional bool field, a problem if it's there but isn't a bool
+pub fn bool_field(data: &serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Option<bool> {
+    let value = data.get(key)?;
+    if value.as_bool().is_none() {
+        problems.push(SceneProblem::new(key, "expected true or false"));
+    }
+    value.as_bool()
+}
+
+/// Optional uuid field, a problem if it's there but isn't a uuid string
+pub fn uuid_field(data: &serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Option<Uuid> {
+    let value = data.get(key)?;
+    let uuid = crate::try_uuid!(value);
+    if uuid.is_none() {
+        problems.push(SceneProblem::new(key, "expected a uuid string"));
+    }
+    uuid
+}
+
+/// Optional object field, a problem if it's there but isn't an object
+pub fn object_field<'a>(data: &'a serde_json::Map<String, serde_json::Value>, key: &str, problems: &mut Vec<SceneProblem>) -> Option<&'a serde_json::Map<String, serde_json::Value>> {
+    let value = data.get(key)?;
+    if value.as_object().is_none() {
+        problems.push(SceneProblem::new(key, "expected an object"));
+    }
+    value.as_object()
 }
 
 
@@ -148,37 +510,317 @@ pub struct SceneManager {
     pub json_manager: JSONManager,
     default_components: Vec<serde_json::Value>,
     default_elements: Vec<serde_json::Value>,
-    current_scene_name: &'static str
+    /// Scenes switched and pushed to, the last one is current
+    stack: Vec<(String, Overlay)>,
+    scene_sender: Sender<SceneEvent>,
+    scene_receiver: Rc<Receiver<SceneEvent>>,
+    transition: Option<ActiveTransition>,
+    /// Events sent during a transition, handled once it ends
+    waiting: Vec<SceneEvent>,
+    /// Set by init_scenes, scenes added after it are initialised as they're added
+    started: bool
 }
 
 impl SceneManager {
     pub fn new() -> Self {
 
         let dc = vec![serde_json::from_str(r#"{"name":"pom"}"#).unwrap(),  serde_json::from_str(r#"{"name":"input"}"#).unwrap()];
-        let de = vec![serde_json::from_str(r#"{"name":"default_camera", "scale":"12"}"#).unwrap()];
+        let de = vec![serde_json::from_str(r#"{"name":"default_camera", "scale":12}"#).unwrap()];
+        let scene_sender = Sender::new();
         Self {
             scenes: HashMap::new(),
             json_manager: JSONManager::default(),
             default_components: dc,
             default_elements: de,
-            current_scene_name: ""
+            stack: Vec::new(),
+            scene_receiver: scene_sender.new_receiver(),
+            scene_sender: scene_sender,
+            transition: None,
+            waiting: Vec::new(),
+            started: false
         }
     }
-    pub fn set_scene(&mut self, name: &'static str) -> &mut Scene {
-        self.current_scene_name = name;
+    /// Runs init_elements for every scene, and for scenes added from now on
+    pub fn init_scenes(&mut self) {
+        for scene in self.scenes.values_mut() {
+            scene.init_elements();
+        }
+        self.started = true;
+    }
+    /// Makes `name` the only scene on the stack, without running on_exit or on_enter.
+    /// For the first scene, use SceneEvent to change scenes while running
+    pub fn set_scene(&mut self, name: &str) -> &mut Scene {
+        self.stack = vec![(name.to_string(), Overlay::Opaque)];
+        self.pause_frozen_input();
         self.current_scene()
-
     }
     pub fn current_scene(&mut self) -> &mut Scene {
-        self.scenes.get_mut(self.current_scene_name).unwrap()
+        let (name, _) = self.stack.last().expect("no current scene");
+        self.scenes.get_mut(name).unwrap()
+    }
+    pub fn current_scene_name(&self) -> Option<&str> {
+        self.stack.last().map(|(name, _)| name.as_str())
+    }
+    /// Scenes that are drawn, bottom first. Everything above the top opaque scene
+    pub fn visible_scenes(&self) -> Vec<&Scene> {
+        self.visible_names().iter().filter_map(|name| self.scenes.get(name)).collect()
+    }
+    fn visible_names(&self) -> Vec<String> {
+        let lowest = self.stack.iter().rposition(|(_, overlay)| *overlay == Overlay::Opaque).unwrap_or(0);
+        self.stack[lowest..].iter().map(|(name, _)| name.clone()).collect()
+    }
+    /// Scenes to draw this frame, bottom first, with their opacity and whether they're updated.
+    /// Frozen scenes don't move between updates, so they shouldn't be interpolated.
+    /// Crossfades fade in the scenes appearing, or fade out the ones going if none appear
+    pub fn scenes_to_draw(&self) -> Vec<(&Scene, f32, bool)> {
+        let visible = self.visible_names();
+        let updating = self.updating_scenes();
+        let (from, progress) = match (&self.transition, self.transition()) {
+            (Some(active), Some((Transition::Crossfade(_), progress))) => (active.from.clone(), progress),
+            _ => (visible.clone(), 1.0)
+        };
+
+        let mut draw = Vec::new();
+        let appearing: Vec<&String> = visible.iter().filter(|name| !from.contains(*name)).collect();
+        for name in &from {
+            let alpha = match visible.contains(name) {
+                true => 1.0,
+                false if appearing.is_empty() => 1.0 - progress,
+                false => 1.0
+            };
+            draw.push((name, alpha));
+        }
+        for name in appearing {
+            draw.push((name, progress));
+        }
+        draw.into_iter().filter_map(|(name, alpha)| Some((self.scenes.get(name)?, alpha, updating.contains(name)))).collect()
+    }
+    /// Names of the scenes that are updated, bottom first. The current one and
+    /// those below it that aren't frozen by a scene above
+    fn running_scenes(&self) -> Vec<String> {
+        let lowest = self.stack.iter().rposition(|(_, overlay)| *overlay != Overlay::Passthrough).unwrap_or(0);
+        self.stack[lowest..].iter().map(|(name, _)| name.clone()).collect()
+    }
+    /// Sends scene events from outside a scene, elements use the "scenes" component
+    pub fn scene_sender(&self) -> Sender<SceneEvent> {
+        self.scene_sender.clone()
+    }
+    /// The running transition and how far through it is, from 0.0 to 1.0
+    pub fn transition(&self) -> Option<(Transition, f32)> {
+        let active = self.transition.as_ref()?;
+        let duration = active.transition.duration();
+        Some((active.transition, if duration > 0.0 { (active.elapsed / duration).min(1.0) } else { 1.0 }))
+    }
+    /// Updates the current scene, then handles the scene events sent.
+    /// `seconds` is the length of the update, for transitions.
+    /// Events that couldn't be handled are dropped and returned as errors
+    pub fn update(&mut self, td: f32, seconds: f32) -> Result<(), Vec<SceneChangeError>> {
+        if let Some(active) = &mut self.transition {
+            active.elapsed += seconds;
+            let duration = active.transition.duration();
+            // fades change scene when the screen is covered
+            if active.elapsed >= duration / 2.0 {
+                if let Some(change) = active.change.take() {
+                    self.change_scene(change);
+                }
+            }
+            if self.transition.as_ref().is_some_and(|active| active.elapsed >= duration) {
+                self.transition = None;
+            }
+        }
+
+        for name in self.updating_scenes() {
+            if let Some(scene) = self.scenes.get_mut(&name) {
+                scene.update_elements(td);
+            }
+        }
+
+        self.waiting.append(&mut self.scene_receiver.poll());
+        let mut errors = Vec::new();
+        while self.transition.is_none() && !self.waiting.is_empty() {
+            let event = self.waiting.remove(0);
+            if let Err(e) = self.handle_event(event) {
+                errors.push(e);
+            }
+        }
+        self.pause_frozen_input();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+    /// Running scenes, or none while the old scenes stay still fading out
+    fn updating_scenes(&self) -> Vec<String> {
+        match &self.transition {
+            Some(ActiveTransition { change: Some(_), .. }) => Vec::new(),
+            _ => self.running_scenes()
+        }
     }
-    pub fn add_scene(&mut self, data: serde_json::Value) {
+    /// Input sent to a frozen scene would pile up and replay when it resumes, so it's dropped
+    fn pause_frozen_input(&self) {
+        let updating = self.updating_scenes();
+        for (name, scene) in &self.scenes {
+            scene.component_manager.access("input", |input: &Sender<InputEvent>| input.set_paused(!updating.contains(name)));
+        }
+    }
+    /// Changes scene or starts a transition that will
+    fn handle_event(&mut self, event: SceneEvent) -> Result<(), SceneChangeError> {
+        let transition = match &event {
+            SceneEvent::SetCamera(uuid) => {
+                self.current_scene().camera_uuid = *uuid;
+                return Ok(());
+            },
+            SceneEvent::Switch(name, transition) | SceneEvent::Push(name, _, transition) => {
+                if !self.scenes.contains_key(name) {
+                    return Err(SceneChangeError::UnknownScene(name.clone()));
+                }
+                // switching replaces the current scene, so it can switch to itself
+                let kept = match event {
+                    SceneEvent::Switch(..) => self.stack.len().saturating_sub(1),
+                    _ => self.stack.len()
+                };
+                if self.stack[..kept].iter().any(|(on_stack, _)| on_stack == name) {
+                    return Err(SceneChangeError::AlreadyOnStack(name.clone()));
+                }
+                *transition
+            },
+            SceneEvent::Pop(transition) => {
+                if self.stack.len() < 2 {
+                    return Err(SceneChangeError::NothingToPop);
+                }
+                *transition
+            }
+        };
+
+        match transition {
+            Transition::None => self.change_scene(event),
+            Transition::Fade(_) => self.transition = Some(ActiveTransition { transition: transition, elapsed: 0.0, change: Some(event), from: Vec::new() }),
+            Transition::Crossfade(_) => {
+                let from = self.visible_names();
+                self.change_scene(event);
+                self.transition = Some(ActiveTransition { transition: transition, elapsed: 0.0, change: None, from: from });
+            }
+        }
+        Ok(())
+    }
+    /// Scenes run on_enter when they're switched or pushed to, and on_exit when they're switched from or popped.
+    /// Switching keeps the overlay of the scene it replaces
+    fn change_scene(&mut self, event: SceneEvent) {
+        match event {
+            SceneEvent::Switch(name, _) => {
+                let mut overlay = Overlay::Opaque;
+                if let Some((previous, previous_overlay)) = self.stack.pop() {
+                    overlay = previous_overlay;
+                    if let Some(scene) = self.scenes.get_mut(&previous) {
+                        scene.exit();
+                    }
+                }
+                self.stack.push((name, overlay));
+            },
+            SceneEvent::Push(name, overlay, _) => self.stack.push((name, overlay)),
+            SceneEvent::Pop(_) => {
+                if let Some((previous, _)) = self.stack.pop() {
+                    if let Some(scene) = self.scenes.get_mut(&previous) {
+                        scene.exit();
+                    }
+                }
+                return;
+            },
+            SceneEvent::SetCamera(_) => return
+        }
+        self.current_scene().enter();
+    }
+    /// Scene in the format add_scene reads, None if there isn't one called `name`
+    pub fn save_scene(&self, name: &str) -> Option<serde_json::Value> {
+        let mut data = self.scenes.get(name)?.save();
+        data["name"] = name.into();
+        Some(data)
+    }
+    pub fn save_current_scene(&self) -> Option<serde_json::Value> {
+        self.save_scene(self.current_scene_name()?)
+    }
+    /// Adds nothing if there are any problems with the scene
+    pub fn add_scene(&mut self, data: serde_json::Value) -> Result<(), SceneError> {
         let mut scene_name = "".to_string();
         if let serde_json::Value::Object(ref fields) = data {
             if let Some(serde_json::Value::String(name)) = fields.get("name") {
                 scene_name = name.clone();
             }
         }
-        self.scenes.insert(scene_name, self.json_manager.create_scene(data, self.default_components.clone(), self.default_elements.clone()));
+        let scene = self.build_scene(data)?;
+        self.insert_scene(scene_name, scene);
+        Ok(())
+    }
+    fn insert_scene(&mut self, name: String, mut scene: Scene) {
+        if self.started {
+            scene.init_elements();
+        }
+        self.scenes.insert(name, scene);
+    }
+    fn build_scene(&self, data: serde_json::Value) -> Result<Scene, SceneError> {
+        let mut scene = self.json_manager.create_scene(data, self.default_components.clone(), self.default_elements.clone())?;
+        scene.component_manager.add("scenes", Box::new(self.scene_sender.clone()));
+        Ok(scene)
+    }
+    /// Adds the scene in a JSON file, named after the file if it has no "name". Returns the name
+    pub fn add_scene_from_path(&mut self, path: impl AsRef<Path>) -> Result<String, SceneFileError> {
+        let (name, scene) = self.read_scene_file(path.as_ref())?;
+        self.insert_scene(name.clone(), scene);
+        Ok(name)
+    }
+    /// Adds every *.json file in `dir` as a scene, in file name order. Returns their names.
+    /// Adds none of them if any can't be added, and returns what's wrong with each
+    pub fn add_scenes_from_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<String>, Vec<SceneFileError>> {
+        let dir = dir.as_ref();
+        let read_error = |e| vec![SceneFileError { path: dir.to_path_buf(), kind: SceneFileErrorKind::Read(e) }];
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut scenes = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            match self.read_scene_file(&path) {
+                Ok(scene) => scenes.push(scene),
+                Err(e) => errors.push(e)
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut names = Vec::new();
+        for (name, scene) in scenes {
+            self.insert_scene(name.clone(), scene);
+            names.push(name);
+        }
+        Ok(names)
+    }
+    fn read_scene_file(&self, path: &Path) -> Result<(String, Scene), SceneFileError> {
+        let error = |kind| SceneFileError { path: path.to_path_buf(), kind: kind };
+        let text = std::fs::read_to_string(path).map_err(|e| error(SceneFileErrorKind::Read(e)))?;
+        let mut data: serde_json::Value = serde_json::from_str(&text).map_err(|e| error(SceneFileErrorKind::Json(e)))?;
+        let problem = |path: &str, message: &str| error(SceneFileErrorKind::Scene(SceneError { problems: vec![SceneProblem::new(path, message)] }));
+
+        let fields = data.as_object_mut().ok_or_else(|| problem("", "expected an object"))?;
+        let name = match fields.get("name") {
+            Some(serde_json::Value::String(name)) => name.clone(),
+            Some(_) => return Err(problem("name", "expected a string")),
+            None => {
+                let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+                fields.insert("name".to_string(), stem.clone().into());
+                stem
+            }
+        };
+
+        let scene = self.build_scene(data).map_err(|e| error(SceneFileErrorKind::Scene(e)))?;
+        Ok((name, scene))
     }
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+#version 140
+
+out vec4 color;
+
+in vec4 v_color;
+
+
+void main() {
+    color = v_color;
+}
\ No newline at end of file
@@ -0,0 +1,19 @@
+#version 140
+
+in vec2 pos;
+in vec4 color;
+
+uniform mat3 ortho_mat;
+uniform vec2 translation;
+
+
+out vec4 v_color;
+
+
+void main() {
+    v_color = color;
+    vec3 translated = vec3(pos - translation, 0.0);
+    vec3 clipped = ortho_mat * translated;
+
+    gl_Position = vec4(clipped.xy, 0.0, 1.0);
+}
\ No newline at end of file
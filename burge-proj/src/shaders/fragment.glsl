@@ -2,6 +2,7 @@
 
 out vec4 color;
 uniform sampler2D tex;
+uniform float alpha;
 
 in vec2 v_tex_coords;
 
@@ -11,6 +12,7 @@ in vec2 v_tex_coords;
 void main() {
 
     color = texture(tex[0], v_tex_coords);
+    color.a *= alpha;
     //color.rgb *= v_brightness;
     //color = vec4(0.5,0.5,0.0,1.0);
 
@@ -22,6 +22,15 @@ impl Sprite {
         self.scale = scale;
         self
     }
+    pub fn pos(&self) -> [f32;2] {
+        self.pos
+    }
+    /// Moves the sprite back towards `previous`, alpha of 1.0 leaves it in place
+    pub fn interpolated(mut self, previous: [f32;2], alpha: f32) -> Self {
+        self.pos[0] = previous[0] + (self.pos[0] - previous[0])*alpha;
+        self.pos[1] = previous[1] + (self.pos[1] - previous[1])*alpha;
+        self
+    }
 }
 
 
@@ -0,0 +1,1013 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use burge_proj::component::{Component, ComponentManager};
+use burge_proj::element::{Element, ElementBase};
+use burge_proj::event::{Receiver, Sender};
+use burge_proj::physics::{box_collider, ramp_collider, Collider, CombineRule, ContactSummary, ConvexPolygon, FieldKind, ForceField, Joint, JointKind, KinematicPath, POMComponent, POMSettings, POSettings, PhysEvent, PhysObj, PhysObjManager, PhysSnapshot};
+use burge_proj::scene::Scene;
+use serde_json::json;
+use uuid::Uuid;
+
+
+/// Dynamic that applies corrections in local_update and sends itself in post_update, like Player
+#[derive(Clone)]
+struct Body {
+    hb: PhysObj,
+    uuid: Uuid,
+    phys_sender: Sender<PhysEvent>,
+    phys_receiver: Rc<Receiver<PhysEvent>>,
+    pos: Rc<Cell<[f32;2]>>,
+    summary: Rc<Cell<ContactSummary>>,
+    /// Everything the POM sent it
+    events: Rc<RefCell<Vec<PhysEvent>>>
+}
+
+impl Body {
+    fn new(hb: PhysObj) -> (Self, Rc<Cell<[f32;2]>>) {
+        let pos = Rc::new(Cell::new(hb.pos));
+        (Self { hb: hb, uuid: Uuid::nil(), phys_sender: Sender::new(), phys_receiver: Receiver::new(), pos: pos.clone(), summary: Default::default(), events: Default::default() }, pos)
+    }
+}
+
+impl ElementBase for Body {
+    fn init(&mut self, uuid: Uuid, components: &ComponentManager) {
+        self.uuid = uuid;
+        components.access("pom", |pom: &POMComponent| {
+            self.phys_sender = pom.new_sender();
+            (_, self.phys_receiver) = pom.new_receiver_uuid(uuid);
+        });
+    }
+    fn local_update(&mut self, td: f32) {
+        for e in self.phys_receiver.poll() {
+            self.events.borrow_mut().push(e);
+            match e {
+                PhysEvent::ModPos(p) => self.hb.pos = [self.hb.pos[0] + p[0], self.hb.pos[1] + p[1]],
+                PhysEvent::ModVelocity(v) => self.hb.delta = [self.hb.delta[0] + v[0], self.hb.delta[1] + v[1]],
+                PhysEvent::Contacts(summary) => self.summary.set(summary),
+                _ => ()
+            }
+        }
+        self.hb.pos = [self.hb.pos[0] + self.hb.delta[0]*td, self.hb.pos[1] + self.hb.delta[1]*td];
+        self.pos.set(self.hb.pos);
+    }
+    fn post_update(&mut self) {
+        self.phys_sender.send(PhysEvent::DynamicPO(self.uuid, self.hb));
+    }
+}
+
+
+fn scene_with_block() -> Scene {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut block = box_collider([-2.0, -1.0], [4.0, 1.0]);
+    block.settings.restitution = 0.0;
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), block));
+    });
+    scene
+}
+
+#[test]
+fn falling_body_comes_to_rest_on_block() {
+    let mut scene = scene_with_block();
+    let mut hb = box_collider([0.0, 2.0], [1.0, 1.0]);
+    hb.settings.restitution = 0.0;
+    let (body, pos) = Body::new(hb);
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..120 {
+        scene.update_elements(1.0);
+    }
+    let rest = pos.get();
+    for _ in 0..120 {
+        scene.update_elements(1.0);
+        let [x, y] = pos.get();
+        assert!(x == rest[0] && (y - rest[1]).abs() < 1e-4, "still moving at {:?}, rested at {:?}", [x, y], rest);
+    }
+    // on top of the block, allowing for the tick it takes a correction to arrive
+    assert!(rest[1].abs() < 0.01, "rested at {:?}", rest);
+}
+
+#[test]
+fn long_raycasts_finish_and_infinite_ones_are_rejected() {
+    let mut scene = scene_with_block();
+    scene.init_elements();
+    scene.update_elements(1.0);
+
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        let hit = pom.raycast([0.0, 5.0], [0.0, -1.0], 1.0e9, Default::default()).unwrap();
+        assert_eq!(hit.uuid, Uuid::from_u128(1));
+        assert!((hit.distance - 5.0).abs() < 1e-4);
+        assert!(pom.raycast([0.0, 5.0], [0.0, -1.0], f32::INFINITY, Default::default()).is_none());
+        assert!(pom.raycast([0.0, 5.0], [0.0, -1.0], f32::NAN, Default::default()).is_none());
+    });
+}
+
+/// State hash after a seeded run of a body falling onto the block, with an extra static at `ledge`
+fn seeded_run_hash(seed: u64, ledge: [f32;2]) -> u64 {
+    burge_proj::ids::seed(seed);
+    let mut scene = scene_with_block();
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(2), box_collider(ledge, [1.0, 1.0])));
+    });
+    let (body, _) = Body::new(box_collider([0.0, 2.0], [1.0, 1.0]));
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+    burge_proj::ids::unseed();
+
+    let mut hash = 0;
+    scene.component_manager.access("pom", |pom: &POMComponent| hash = pom.state_hash());
+    hash
+}
+
+#[test]
+fn seeded_runs_hash_the_same() {
+    assert_eq!(seeded_run_hash(7, [20.0, 0.0]), seeded_run_hash(7, [20.0, 0.0]));
+    // the ledge never touches the body, but it's part of the world
+    assert_ne!(seeded_run_hash(7, [20.0, 0.0]), seeded_run_hash(7, [30.0, 0.0]));
+}
+
+/// Kinematic on a path that sends itself every tick, like a moving platform element
+#[derive(Clone)]
+struct Platform {
+    hb: PhysObj,
+    uuid: Uuid,
+    phys_sender: Sender<PhysEvent>,
+    phys_receiver: Rc<Receiver<PhysEvent>>,
+    path: Option<KinematicPath>,
+    pos: Rc<Cell<[f32;2]>>
+}
+
+impl ElementBase for Platform {
+    fn init(&mut self, uuid: Uuid, components: &ComponentManager) {
+        self.uuid = uuid;
+        components.access("pom", |pom: &POMComponent| {
+            self.phys_sender = pom.new_sender();
+            (_, self.phys_receiver) = pom.new_receiver_uuid(uuid);
+        });
+        self.phys_sender.send(PhysEvent::KinematicPO(uuid, self.hb));
+        if let Some(path) = self.path {
+            self.phys_sender.send(PhysEvent::SetKinematicPath(uuid, path));
+        }
+    }
+    fn local_update(&mut self, _td: f32) {
+        for e in self.phys_receiver.poll() {
+            if let PhysEvent::ModPos(p) = e {
+                self.hb.pos = [self.hb.pos[0] + p[0], self.hb.pos[1] + p[1]];
+            }
+        }
+        self.pos.set(self.hb.pos);
+    }
+    fn post_update(&mut self) {
+        self.phys_sender.send(PhysEvent::KinematicPO(self.uuid, self.hb));
+    }
+}
+
+#[test]
+fn resent_kinematic_keeps_its_path() {
+    let mut scene = scene_with_block();
+    let pos = Rc::new(Cell::new([0.0, 5.0]));
+    let path = KinematicPath::new(&[[0.0, 5.0], [10.0, 5.0]], 0.1, false);
+    let platform = Platform { hb: box_collider([0.0, 5.0], [2.0, 0.5]), uuid: Uuid::nil(), phys_sender: Sender::new(), phys_receiver: Receiver::new(), path: Some(path), pos: pos.clone() };
+    scene.add_element(Element::Generic(Box::new(platform)));
+    scene.init_elements();
+
+    for _ in 0..21 {
+        scene.update_elements(1.0);
+    }
+    assert!((pos.get()[0] - 2.0).abs() < 1e-3, "platform at {:?}", pos.get());
+}
+
+#[test]
+fn kinematic_paths_report_bad_points() {
+    let data = json!({ "points": [[0.0, 5.0], [10.0, "up"]], "looping": 1 });
+    let problems: Vec<String> = KinematicPath::try_load(data.as_object().unwrap()).unwrap_err().iter().map(|p| p.to_string()).collect();
+    assert_eq!(problems, vec!["points[1]: expected [x, y]", "speed: missing", "looping: expected true or false"]);
+
+    let path = KinematicPath::new(&[[0.0, 5.0], [10.0, 5.0]], 0.1, true);
+    assert_eq!(KinematicPath::load(path.save().as_object().unwrap()), Some(path));
+}
+
+#[test]
+fn ground_velocity_is_per_td_like_delta() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut hb = box_collider([-4.0, 0.0], [8.0, 0.5]);
+    hb.delta = [0.1, 0.0];
+    let platform = Platform { hb: hb, uuid: Uuid::nil(), phys_sender: Sender::new(), phys_receiver: Receiver::new(), path: None, pos: Default::default() };
+    scene.add_element(Element::Generic(Box::new(platform)));
+    let mut hb = box_collider([0.0, 0.5], [1.0, 1.0]);
+    hb.settings.restitution = 0.0;
+    let (body, _) = Body::new(hb);
+    let summary = body.summary.clone();
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..10 {
+        scene.update_elements(2.0);
+    }
+    let summary = summary.get();
+    assert!(summary.grounded);
+    assert!((summary.ground_velocity[0] - 0.1).abs() < 1e-6, "ground velocity {:?}", summary.ground_velocity);
+
+    let mut data = summary.save();
+    assert_eq!(ContactSummary::load(data.as_object().unwrap()), Some(summary));
+    data["left"] = json!("yes");
+    data["normals"][0] = json!([0.0]);
+    let problems: Vec<String> = ContactSummary::try_load(data.as_object().unwrap()).unwrap_err().iter().map(|p| p.to_string()).collect();
+    assert_eq!(problems, vec!["left: expected true or false", "normals[0]: expected [x, y]"]);
+}
+
+#[test]
+fn settings_save_names_and_report_unknown_ones() {
+    let mut scene = Scene::default();
+    let layers = vec!["default".to_string(), "player".to_string()];
+    scene.add_component("pom", Box::new(PhysObjManager::with_settings(POMSettings { layers: layers, ..Default::default() })));
+
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        let data = json!({ "layer": "player", "mask": ["default", "player", 64], "behaviours": ["stop"] });
+        let settings = pom.load_settings(&POSettings::default(), data.as_object().unwrap()).unwrap();
+        let saved = pom.save_settings(&settings);
+        assert_eq!(saved["layer"], json!(["player"]));
+        assert_eq!(saved["mask"], json!(["default", "player", 64]));
+        assert_eq!(saved["behaviours"], json!(["stop"]));
+        let loaded = pom.load_settings(&POSettings::default(), saved.as_object().unwrap()).unwrap();
+        assert_eq!((loaded.layer, loaded.mask, loaded.behaviours), (settings.layer, settings.mask, settings.behaviours));
+
+        let data = json!({ "layer": "ghost", "behaviours": ["stop", "explode"] });
+        let problems = match pom.load_settings(&POSettings::default(), data.as_object().unwrap()) {
+            Ok(_) => panic!("unknown names were loaded"),
+            Err(problems) => problems
+        };
+        let paths: Vec<&str> = problems.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["layer", "behaviours[1]"]);
+    });
+}
+
+#[test]
+fn only_convex_polygons_are_accepted() {
+    let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    assert!(ConvexPolygon::try_new(&square).is_some());
+    let mut clockwise = square;
+    clockwise.reverse();
+    assert!(ConvexPolygon::try_new(&clockwise).is_some());
+
+    assert!(ConvexPolygon::try_new(&[[0.0, 0.0], [4.0, 0.0], [2.0, 1.0], [2.0, 4.0]]).is_none());
+    let star: Vec<[f32;2]> = (0..5).map(|i| {
+        let angle = i as f32 * 2.0 * std::f32::consts::TAU / 5.0;
+        [angle.cos(), angle.sin()]
+    }).collect();
+    assert!(ConvexPolygon::try_new(&star).is_none());
+    let nine: Vec<[f32;2]> = (0..9).map(|i| {
+        let angle = i as f32 * std::f32::consts::TAU / 9.0;
+        [angle.cos(), angle.sin()]
+    }).collect();
+    assert!(ConvexPolygon::try_new(&nine).is_none());
+
+    assert!(ConvexPolygon::try_new(&[]).is_none());
+    assert!(ConvexPolygon::try_new(&[[0.0, 0.0], [1.0, 1.0]]).is_none());
+    assert!(ConvexPolygon::try_new(&[[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]).is_none(), "no area");
+    for verts in [json!([]), json!([[0, 0], [1, 1]])] {
+        let problems = Collider::try_load(json!({ "kind": "polygon", "verts": verts }).as_object().unwrap()).unwrap_err();
+        assert_eq!(problems.iter().map(|p| p.to_string()).collect::<Vec<_>>(), vec!["verts: at least 3 vertices"]);
+    }
+}
+
+#[test]
+fn huge_statics_skip_the_grid_and_still_collide() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    // millions of cells at the default cell size
+    let floor = box_collider([-1.0e7, -1.0e7], [2.0e7, 1.0e7]);
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), floor));
+    });
+    let (body, pos) = Body::new(box_collider([0.0, 2.0], [1.0, 1.0]));
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+    assert!(pos.get()[1].abs() < 0.01, "body at {:?}", pos.get());
+
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::RemoveStaticPO(Uuid::from_u128(1)));
+    });
+    for _ in 0..10 {
+        scene.update_elements(1.0);
+    }
+    assert!(pos.get()[1] < -0.1, "body at {:?}", pos.get());
+}
+
+#[test]
+fn zero_cell_size_loads_and_checks_every_static() {
+    let data = json!({ "cell_size": 0.0 });
+    let pom = match Component::try_load(&PhysObjManager::new(), data.as_object().unwrap()) {
+        Ok(pom) => pom,
+        Err(problems) => panic!("{:?}", problems.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+    };
+    assert_eq!(pom.save()["cell_size"], json!(0.0));
+    assert!(Component::try_load(&PhysObjManager::new(), json!({ "cell_size": -1.0 }).as_object().unwrap()).is_err());
+
+    let mut scene = Scene::default();
+    scene.add_component("pom", pom);
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), box_collider([-2.0, -1.0], [4.0, 1.0])));
+    });
+    let (body, pos) = Body::new(box_collider([0.0, 2.0], [1.0, 1.0]));
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+    assert!(pos.get()[1].abs() < 0.01, "body at {:?}", pos.get());
+}
+
+#[test]
+fn static_delta_is_a_velocity_not_an_offset() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut belt = box_collider([-2.0, -1.0], [4.0, 1.0]);
+    belt.delta = [3.0, 0.0];
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), belt));
+    });
+    let (body, pos) = Body::new(box_collider([-0.5, 2.0], [1.0, 1.0]));
+    let summary = body.summary.clone();
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+    assert!(pos.get()[1].abs() < 0.01, "body at {:?}", pos.get());
+    assert!(summary.get().grounded);
+    assert_eq!(summary.get().ground_velocity, [3.0, 0.0]);
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        let hit = pom.raycast([-1.5, 5.0], [0.0, -1.0], 10.0, Default::default()).unwrap();
+        assert_eq!(hit.uuid, Uuid::from_u128(1));
+    });
+}
+
+#[test]
+fn joints_cant_pull_bodies_into_walls() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), box_collider([1.5, -5.0], [10.0, 10.0])));
+        // the rope's far end is inside the wall
+        pom.add_joint(Joint::new(Uuid::from_u128(2), Uuid::nil(), JointKind::Rope).with_anchors([0.0, 0.0], [5.0, 0.0]).with_length(1.0));
+    });
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.settings.gravity_strength = 0.0;
+    let (body, pos) = Body::new(hb);
+    scene.add_element_with_uuid(Uuid::from_u128(2), Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..30 {
+        scene.update_elements(1.0);
+        assert!(pos.get()[0] + 1.0 <= 1.5 + 1e-3, "body at {:?} is in the wall", pos.get());
+    }
+}
+
+#[test]
+fn kinematics_can_be_joint_ends() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut hb = box_collider([0.0, 5.0], [1.0, 0.5]);
+    hb.delta = [0.1, 0.0];
+    let platform = Platform { hb: hb, uuid: Uuid::nil(), phys_sender: Sender::new(), phys_receiver: Receiver::new(), path: None, pos: Default::default() };
+    scene.add_element_with_uuid(Uuid::from_u128(1), Element::Generic(Box::new(platform)));
+    let mut hb = box_collider([0.0, 2.0], [1.0, 1.0]);
+    hb.settings.gravity_strength = 0.0;
+    let (body, pos) = Body::new(hb);
+    scene.add_element_with_uuid(Uuid::from_u128(2), Element::Generic(Box::new(body)));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.add_joint(Joint::new(Uuid::from_u128(2), Uuid::from_u128(1), JointKind::Pin).with_anchors([0.0, 0.0], [0.0, -3.0]));
+    });
+    scene.init_elements();
+
+    for _ in 0..21 {
+        scene.update_elements(1.0);
+    }
+    // hanging under the platform, allowing for the tick corrections take to arrive
+    assert!((pos.get()[0] - 2.0).abs() < 0.15 && (pos.get()[1] - 2.0).abs() < 0.15, "body at {:?}", pos.get());
+}
+
+#[test]
+fn stop_floors_stop_bodies_at_any_td() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut floor = box_collider([-20.0, -1.0], [40.0, 1.0]);
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        floor.settings.behaviours = pom.behaviour("stop").unwrap();
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), floor));
+    });
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.delta = [0.05, 0.0];
+    let (body, pos) = Body::new(hb);
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..10 {
+        scene.update_elements(0.5);
+    }
+    let before = pos.get();
+    scene.update_elements(0.5);
+    assert!((pos.get()[0] - before[0]).abs() < 1e-6, "still moving from {:?} to {:?}", before, pos.get());
+    assert!(pos.get()[1].abs() < 0.01, "body at {:?}", pos.get());
+}
+
+/// Uuids of the objects in the Collision events `events` got
+fn collisions(events: &RefCell<Vec<PhysEvent>>) -> Vec<Uuid> {
+    events.borrow().iter().filter_map(|e| match e {
+        PhysEvent::Collision(uuid, _) => Some(*uuid),
+        _ => None
+    }).collect()
+}
+
+#[test]
+fn dynamics_collide_and_the_lighter_one_gives_way() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let body = |pos: [f32;2], delta: f32, mass: f32| {
+        let mut hb = box_collider(pos, [1.0, 1.0]);
+        hb.delta = [delta, 0.0];
+        hb.settings.gravity_strength = 0.0;
+        hb.settings.mass = mass;
+        Body::new(hb)
+    };
+    let (light, light_pos) = body([0.0, 0.0], 0.1, 1.0);
+    let (heavy, heavy_pos) = body([3.0, 0.0], -0.1, 3.0);
+    let (light_events, heavy_events) = (light.events.clone(), heavy.events.clone());
+    scene.add_element_with_uuid(Uuid::from_u128(1), Element::Generic(Box::new(light)));
+    scene.add_element_with_uuid(Uuid::from_u128(2), Element::Generic(Box::new(heavy)));
+    scene.init_elements();
+
+    // one tick of both moving into each other, before the corrections arrive
+    for _ in 0..30 {
+        scene.update_elements(1.0);
+        assert!(heavy_pos.get()[0] - light_pos.get()[0] >= 1.0 - 0.25, "overlapping at {:?} and {:?}", light_pos.get(), heavy_pos.get());
+    }
+    assert!(collisions(&light_events).contains(&Uuid::from_u128(2)));
+    assert!(collisions(&heavy_events).contains(&Uuid::from_u128(1)));
+
+    // the heavy one keeps going and carries the light one back with it
+    let before = (light_pos.get(), heavy_pos.get());
+    scene.update_elements(1.0);
+    assert!(light_pos.get()[0] < before.0[0] && heavy_pos.get()[0] < before.1[0], "moved from {:?} to {:?}", before, (light_pos.get(), heavy_pos.get()));
+}
+
+#[test]
+fn statics_are_replaced_moved_and_removed_by_uuid() {
+    let mut scene = scene_with_block();
+    scene.init_elements();
+    let send = |scene: &mut Scene, e: PhysEvent| {
+        scene.component_manager.access("pom", |pom: &POMComponent| pom.new_sender().send(e));
+        scene.update_elements(1.0);
+    };
+    let found = |scene: &Scene, min: [f32;2], max: [f32;2]| {
+        let mut found = Vec::new();
+        scene.component_manager.access("pom", |pom: &POMComponent| found = pom.overlap_aabb(min, max, Default::default()));
+        found
+    };
+    let block = Uuid::from_u128(1);
+
+    // replacing leaves nothing behind where it was
+    send(&mut scene, PhysEvent::StaticPO(block, box_collider([10.0, 0.0], [1.0, 1.0])));
+    assert_eq!(found(&scene, [-1.0, -0.5], [1.0, -0.4]), vec![]);
+    assert_eq!(found(&scene, [10.2, 0.2], [10.8, 0.8]), vec![block]);
+
+    send(&mut scene, PhysEvent::MoveStaticPO(block, [20.0, 0.0]));
+    assert_eq!(found(&scene, [10.2, 0.2], [10.8, 0.8]), vec![]);
+    assert_eq!(found(&scene, [20.2, 0.2], [20.8, 0.8]), vec![block]);
+
+    send(&mut scene, PhysEvent::RemoveStaticPO(block));
+    assert_eq!(found(&scene, [-100.0, -100.0], [100.0, 100.0]), vec![]);
+
+    // nil statics each get their own uuid
+    send(&mut scene, PhysEvent::StaticPO(Uuid::nil(), box_collider([0.0, 0.0], [1.0, 1.0])));
+    send(&mut scene, PhysEvent::StaticPO(Uuid::nil(), box_collider([0.0, 0.0], [1.0, 1.0])));
+    let nil = found(&scene, [0.2, 0.2], [0.8, 0.8]);
+    assert_eq!(nil.len(), 2);
+    assert!(nil[0] != nil[1] && !nil.contains(&Uuid::nil()));
+}
+
+#[test]
+fn masks_let_objects_pass_through_layers_they_skip() {
+    let mut scene = Scene::default();
+    let layers = vec!["default".to_string(), "ground".to_string(), "ghost".to_string()];
+    scene.add_component("pom", Box::new(PhysObjManager::with_settings(POMSettings { layers: layers, ..Default::default() })));
+    let mut bodies = Vec::new();
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        let mut floor = box_collider([-5.0, -1.0], [6.0, 1.0]);
+        floor.settings.layer = pom.layer("ground").unwrap();
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), floor));
+        let mut fence = box_collider([1.5, -1.0], [3.0, 1.0]);
+        fence.settings.layer = pom.layer("ground").unwrap();
+        fence.settings.mask = pom.layer("default").unwrap();
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(2), fence));
+
+        let mut walker = box_collider([-2.0, 2.0], [1.0, 1.0]);
+        walker.settings.mask = pom.layer("ground").unwrap();
+        // a ghost that skips the ground, and one over the fence, which skips ghosts
+        let mut ghost = box_collider([0.0, 2.0], [1.0, 1.0]);
+        ghost.settings.layer = pom.layer("ghost").unwrap();
+        ghost.settings.mask = pom.layer("default").unwrap();
+        let mut fenced = box_collider([2.0, 2.0], [1.0, 1.0]);
+        fenced.settings.layer = pom.layer("ghost").unwrap();
+        bodies = vec![walker, ghost, fenced];
+    });
+    let mut seen = Vec::new();
+    for (i, hb) in bodies.into_iter().enumerate() {
+        let (body, pos) = Body::new(hb);
+        seen.push((pos, body.events.clone()));
+        scene.add_element_with_uuid(Uuid::from_u128(10 + i as u128), Element::Generic(Box::new(body)));
+    }
+    scene.init_elements();
+
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+    let (walker, ghost, fenced) = (&seen[0], &seen[1], &seen[2]);
+    assert!(walker.0.get()[1].abs() < 0.01, "walker at {:?}", walker.0.get());
+    assert!(collisions(&walker.1).contains(&Uuid::from_u128(1)));
+    for (pos, events) in [ghost, fenced] {
+        assert!(pos.get()[1] < -1.0, "fell to {:?}", pos.get());
+        assert!(collisions(events).is_empty());
+    }
+}
+
+#[test]
+fn triggers_report_enter_once_stay_while_inside_and_exit() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut zone = box_collider([2.0, -1.0], [2.0, 2.0]);
+    zone.settings.trigger = true;
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), zone));
+    });
+    let mut hb = box_collider([0.0, -0.5], [1.0, 1.0]);
+    hb.delta = [0.25, 0.0];
+    hb.settings.gravity_strength = 0.0;
+    let (body, pos) = Body::new(hb);
+    let events = body.events.clone();
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    // E, S or X for each tick, - for neither
+    let mut ticks = String::new();
+    for _ in 0..30 {
+        events.borrow_mut().clear();
+        scene.update_elements(1.0);
+        let mut tick = '-';
+        for e in events.borrow().iter() {
+            match e {
+                PhysEvent::TriggerEnter(uuid) if *uuid == Uuid::from_u128(1) => tick = 'E',
+                PhysEvent::TriggerStay(uuid) if *uuid == Uuid::from_u128(1) => tick = 'S',
+                PhysEvent::TriggerExit(uuid) if *uuid == Uuid::from_u128(1) => tick = 'X',
+                _ => ()
+            }
+        }
+        ticks.push(tick);
+    }
+    // triggers never block
+    assert!(pos.get()[0] > 6.0, "body at {:?}", pos.get());
+    let edges = ticks.trim_matches('-');
+    assert!(edges.starts_with("ES") && edges.ends_with("SX"), "ticks {}", ticks);
+    assert!(edges[1..edges.len()-1].chars().all(|t| t == 'S'), "ticks {}", ticks);
+}
+
+/// Where a body shot at a thin wall ends up, and whether it hit it
+fn shot_at_thin_wall(continuous: bool) -> ([f32;2], bool) {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), box_collider([5.0, -2.0], [0.1, 4.0])));
+    });
+    let mut hb = box_collider([0.0, -0.25], [0.5, 0.5]);
+    hb.delta = [1.7, 0.0];
+    hb.settings.gravity_strength = 0.0;
+    hb.settings.continuous = continuous;
+    let (body, pos) = Body::new(hb);
+    let events = body.events.clone();
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..10 {
+        scene.update_elements(1.0);
+    }
+    let hit = collisions(&events).contains(&Uuid::from_u128(1));
+    (pos.get(), hit)
+}
+
+#[test]
+fn continuous_bodies_stop_at_thin_walls() {
+    let (pos, hit) = shot_at_thin_wall(false);
+    assert!(pos[0] > 5.1 && !hit, "discrete body at {:?}", pos);
+
+    let (pos, hit) = shot_at_thin_wall(true);
+    assert!(hit);
+    assert!(pos[0] + 0.5 <= 5.0 + 1e-3, "continuous body at {:?}", pos);
+}
+
+#[test]
+fn the_later_combine_rule_wins() {
+    assert_eq!(CombineRule::combine((0.2, CombineRule::Average), (0.6, CombineRule::Average)), 0.4);
+    assert_eq!(CombineRule::combine((0.2, CombineRule::Average), (0.6, CombineRule::Min)), 0.2);
+    assert_eq!(CombineRule::combine((0.5, CombineRule::Multiply), (0.6, CombineRule::Min)), 0.3);
+    assert_eq!(CombineRule::combine((0.2, CombineRule::Max), (0.6, CombineRule::Multiply)), 0.6);
+}
+
+/// Highest point of a ball dropped on a floor, after its first bounce
+fn bounce_height(ball: f32, floor: f32, rule: CombineRule) -> f32 {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut hb = box_collider([-5.0, -1.0], [10.0, 1.0]);
+    hb.settings.restitution = floor;
+    hb.settings.restitution_combine = rule;
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), hb));
+    });
+    let mut hb = box_collider([0.0, 3.0], [1.0, 1.0]);
+    hb.settings.restitution = ball;
+    let (body, pos) = Body::new(hb);
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    let mut landed = false;
+    let mut height: f32 = 0.0;
+    for _ in 0..200 {
+        scene.update_elements(1.0);
+        landed |= pos.get()[1] < 0.1;
+        if landed {
+            height = height.max(pos.get()[1]);
+        }
+    }
+    height
+}
+
+#[test]
+fn restitution_combines_by_rule() {
+    let none = bounce_height(1.0, 0.0, CombineRule::Min);
+    let half = bounce_height(1.0, 0.0, CombineRule::Average);
+    let full = bounce_height(1.0, 0.0, CombineRule::Max);
+    assert!(none < 0.05, "bounced to {}", none);
+    assert!(half > 0.3 && half < 1.5, "bounced to {}", half);
+    assert!(full > 2.5, "bounced to {}", full);
+}
+
+/// How far a body slides along a floor after being pushed, with both at `friction`
+fn slide_distance(friction: f32) -> f32 {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut hb = box_collider([-50.0, -1.0], [100.0, 1.0]);
+    hb.settings.friction = friction;
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), hb));
+    });
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.delta = [0.2, 0.0];
+    hb.settings.friction = friction;
+    let (body, pos) = Body::new(hb);
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..100 {
+        scene.update_elements(1.0);
+    }
+    pos.get()[0]
+}
+
+#[test]
+fn friction_slows_sliding() {
+    let ice = slide_distance(0.0);
+    let rough = slide_distance(1.0);
+    assert!(ice > 19.0, "ice slid {}", ice);
+    // friction takes at most friction * gravity off each tick, so 0.2 stops after about 4
+    assert!(rough > 3.0 && rough < 5.0, "rough slid {}", rough);
+}
+
+#[test]
+fn one_way_platforms_let_bodies_jump_up_through_and_land_on_top() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut platform = box_collider([-2.0, 2.0], [4.0, 0.2]);
+    platform.settings.one_way = Some([0.0, 1.0]);
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(1), platform));
+    });
+    let mut hb = box_collider([-0.5, 0.0], [1.0, 1.0]);
+    hb.delta = [0.0, 0.2];
+    let (body, pos) = Body::new(hb);
+    let summary = body.summary.clone();
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    for _ in 0..200 {
+        scene.update_elements(1.0);
+    }
+    assert!((pos.get()[1] - 2.2).abs() < 0.01, "body at {:?}", pos.get());
+    assert_eq!(summary.get().ground, Some(Uuid::from_u128(1)));
+}
+
+#[test]
+fn bodies_slide_over_block_seams_and_stand_on_slopes() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        for i in 0..20 {
+            pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(i + 1), box_collider([i as f32, -1.0], [1.0, 1.0])));
+        }
+        // 30 degrees, under the default max_slope
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(100), ramp_collider([30.0, 0.0], [4.0, 4.0 * 30f32.to_radians().tan()], true)));
+    });
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.delta = [0.1, 0.0];
+    let (slider, slider_pos) = Body::new(hb);
+    let slider_summary = slider.summary.clone();
+    scene.add_element(Element::Generic(Box::new(slider)));
+    let (stander, stander_pos) = Body::new(box_collider([31.5, 2.0], [1.0, 1.0]));
+    let stander_summary = stander.summary.clone();
+    scene.add_element(Element::Generic(Box::new(stander)));
+    scene.init_elements();
+
+    for _ in 0..30 {
+        scene.update_elements(1.0);
+    }
+    let rest = stander_pos.get();
+    for _ in 0..100 {
+        let before = slider_pos.get();
+        scene.update_elements(1.0);
+        assert!((slider_pos.get()[0] - before[0] - 0.1).abs() < 1e-4 && slider_pos.get()[1].abs() < 0.01, "moved from {:?} to {:?}", before, slider_pos.get());
+        assert!(slider_summary.get().grounded && !slider_summary.get().on_wall());
+
+        assert!((stander_pos.get()[0] - rest[0]).abs() < 1e-4, "slid from {:?} to {:?}", rest, stander_pos.get());
+        assert!(stander_summary.get().grounded);
+    }
+}
+
+/// Distance from `anchor` of a body starting at `start` under gravity, hung from it by `joint`, each tick
+fn hanging_distances(joint: JointKind, start: [f32;2], ticks: usize) -> Vec<f32> {
+    let anchor = [0.0, 10.0];
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.add_joint(Joint::new(Uuid::from_u128(1), Uuid::nil(), joint).with_anchors([0.5, 0.5], anchor).with_length(3.0));
+    });
+    let (body, pos) = Body::new(box_collider(start, [1.0, 1.0]));
+    scene.add_element_with_uuid(Uuid::from_u128(1), Element::Generic(Box::new(body)));
+    scene.init_elements();
+
+    (0..ticks).map(|_| {
+        scene.update_elements(1.0);
+        let [x, y] = pos.get();
+        ((x + 0.5 - anchor[0]).powi(2) + (y + 0.5 - anchor[1]).powi(2)).sqrt()
+    }).collect()
+}
+
+#[test]
+fn joints_hold_their_length_by_kind() {
+    // swinging from the side, allowing for the tick corrections take to arrive
+    let distance = hanging_distances(JointKind::Distance, [2.5, 9.5], 200);
+    assert!(distance.iter().all(|d| (d - 3.0).abs() < 0.1), "distances {:?}", distance);
+
+    // slack until it's pulled taut
+    let rope = hanging_distances(JointKind::Rope, [-0.5, 8.5], 200);
+    assert!(rope[0] < 2.0 && rope.iter().all(|d| *d < 3.1), "distances {:?}", rope);
+    assert!((rope[199] - 3.0).abs() < 0.1, "distances {:?}", rope);
+
+    // pushed out from 2.0, settling a little past 3.0 under gravity
+    let spring = hanging_distances(JointKind::Spring { stiffness: 0.05, damping: 0.2 }, [-0.5, 7.5], 400);
+    let settled = &spring[350..];
+    assert!(settled.iter().all(|d| *d > 3.0 && *d < 3.3 && (d - settled[0]).abs() < 1e-3), "settled at {:?}", settled);
+
+    let pin = hanging_distances(JointKind::Pin, [-0.5, 9.5], 100);
+    assert!(pin[10..].iter().all(|d| *d < 0.1), "distances {:?}", pin);
+}
+
+#[test]
+fn joints_save_and_load() {
+    let joints = [
+        Joint::new(Uuid::from_u128(1), Uuid::from_u128(2), JointKind::Spring { stiffness: 0.5, damping: 0.25 }).with_anchors([0.5, 0.0], [0.0, -1.0]).with_length(2.0),
+        Joint::new(Uuid::from_u128(1), Uuid::nil(), JointKind::Rope)
+    ];
+    for joint in joints {
+        assert_eq!(Joint::load(joint.save().as_object().unwrap()), Some(joint));
+    }
+
+    let problems = Joint::try_load(json!({ "b": "nope", "kind": "glue" }).as_object().unwrap()).unwrap_err();
+    assert_eq!(problems.iter().map(|p| p.to_string()).collect::<Vec<_>>(), vec!["a: missing", "b: expected a uuid string", "kind: unknown joint kind \"glue\""]);
+}
+
+#[test]
+fn debug_lines_show_outlines_contacts_and_joints() {
+    let mut scene = scene_with_block();
+    assert!(Scene::default().debug_display().is_empty());
+    let (body, pos) = Body::new(box_collider([0.0, 0.5], [1.0, 1.0]));
+    scene.add_element_with_uuid(Uuid::from_u128(2), Element::Generic(Box::new(body)));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.add_joint(Joint::new(Uuid::from_u128(2), Uuid::nil(), JointKind::Rope).with_anchors([0.5, 1.0], [0.5, 10.0]).with_length(20.0));
+    });
+    scene.init_elements();
+    for _ in 0..60 {
+        scene.update_elements(1.0);
+    }
+
+    let mut lines = Vec::new();
+    scene.component_manager.access("pom", |pom: &POMComponent| lines = pom.debug_lines());
+    let near = |a: [f32;2], b: [f32;2]| (a[0] - b[0]).abs() < 0.02 && (a[1] - b[1]).abs() < 0.02;
+    let has_line = |from: [f32;2], to: [f32;2]| lines.iter().any(|l| near(l.from, from) && near(l.to, to) || near(l.from, to) && near(l.to, from));
+
+    // the block and the body resting on it
+    assert!(has_line([-2.0, -1.0], [2.0, -1.0]) && has_line([2.0, 0.0], [-2.0, 0.0]));
+    let [x, y] = pos.get();
+    assert!(has_line([x, y], [x + 1.0, y]) && has_line([x + 1.0, y + 1.0], [x, y + 1.0]));
+    // normals of the contacts under it, and the rope
+    assert!(lines.iter().any(|l| l.from[1].abs() < 0.02 && near(l.to, [l.from[0], l.from[1] + 0.3])));
+    assert!(has_line([x + 0.5, y + 1.0], [0.5, 10.0]));
+
+    assert_eq!(scene.debug_display().len(), 2 * lines.len());
+}
+
+#[test]
+fn snapshots_round_trip_through_json_and_restore() {
+    let mut scene = scene_with_block();
+    let mut zone = box_collider([-1.0, 0.0], [1.0, 3.0]);
+    zone.settings.trigger = true;
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        pom.new_sender().send(PhysEvent::StaticPO(Uuid::from_u128(2), zone));
+        pom.add_joint(Joint::new(Uuid::from_u128(3), Uuid::nil(), JointKind::Rope).with_anchors([0.5, 1.0], [0.5, 10.0]));
+        pom.add_field(ForceField::new([-5.0, -5.0], [5.0, 5.0], FieldKind::Wind([0.001, 0.0])));
+    });
+    let path = KinematicPath::new(&[[-4.0, 5.0], [4.0, 5.0]], 0.1, true);
+    let platform = Platform { hb: box_collider([-4.0, 5.0], [2.0, 0.5]), uuid: Uuid::nil(), phys_sender: Sender::new(), phys_receiver: Receiver::new(), path: Some(path), pos: Default::default() };
+    scene.add_element(Element::Generic(Box::new(platform)));
+    let (body, _) = Body::new(box_collider([-1.0, 2.0], [1.0, 1.0]));
+    scene.add_element_with_uuid(Uuid::from_u128(3), Element::Generic(Box::new(body)));
+    scene.init_elements();
+    for _ in 0..20 {
+        scene.update_elements(1.0);
+    }
+
+    let mut snapshot = None;
+    scene.component_manager.access("pom", |pom: &POMComponent| snapshot = Some(pom.snapshot()));
+    let snapshot = snapshot.unwrap();
+    let saved = snapshot.save();
+    let text = saved.to_string();
+    let loaded = PhysSnapshot::load(serde_json::from_str::<serde_json::Value>(&text).unwrap().as_object().unwrap()).unwrap();
+    assert_eq!(loaded.save(), saved);
+    assert_eq!(loaded.state_hash(), snapshot.state_hash());
+
+    // running on and restoring puts everything back
+    for _ in 0..20 {
+        scene.update_elements(1.0);
+    }
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        assert_ne!(pom.state_hash(), snapshot.state_hash());
+        pom.restore(&loaded);
+        assert_eq!(pom.state_hash(), snapshot.state_hash());
+        assert_eq!(pom.snapshot().save(), saved);
+    });
+
+    // and so does loading a "pom" with it
+    let pom = Component::try_load(&PhysObjManager::new(), json!({ "snapshot": saved }).as_object().unwrap()).ok().unwrap();
+    let pom = pom.to_any().downcast_ref::<POMComponent>().unwrap();
+    assert_eq!(pom.state_hash(), snapshot.state_hash());
+}
+
+#[test]
+fn impulses_and_forces_are_divided_by_mass() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.settings.gravity_strength = 0.0;
+    hb.settings.mass = 2.0;
+    let (body, pos) = Body::new(hb);
+    scene.add_element_with_uuid(Uuid::from_u128(1), Element::Generic(Box::new(body)));
+    scene.init_elements();
+    let send = |scene: &Scene, e: PhysEvent| scene.component_manager.access("pom", |pom: &POMComponent| pom.new_sender().send(e));
+
+    // once the POM has the body, allowing a few ticks for the change to arrive
+    scene.update_elements(1.0);
+    send(&scene, PhysEvent::ApplyImpulse(Uuid::from_u128(1), [0.2, 0.0]));
+    for _ in 0..3 {
+        scene.update_elements(1.0);
+    }
+    let before = pos.get();
+    scene.update_elements(1.0);
+    assert!((pos.get()[0] - before[0] - 0.1).abs() < 1e-5, "moved from {:?} to {:?}", before, pos.get());
+
+    // forces are per td
+    send(&scene, PhysEvent::ApplyForce(Uuid::from_u128(1), [0.0, 0.2]));
+    for _ in 0..3 {
+        scene.update_elements(0.5);
+    }
+    let before = pos.get();
+    scene.update_elements(1.0);
+    assert!((pos.get()[1] - before[1] - 0.05).abs() < 1e-5, "moved from {:?} to {:?}", before, pos.get());
+}
+
+/// Velocity after `ticks` of a body with `mass` starting at `pos` in a pom loaded from `data`
+fn velocity_in(data: serde_json::Value, pos: [f32;2], mass: f32, ticks: usize) -> [f32;2] {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Component::try_load(&PhysObjManager::new(), data.as_object().unwrap()).ok().unwrap());
+    let mut hb = box_collider(pos, [1.0, 1.0]);
+    hb.settings.mass = mass;
+    hb.settings.terminal_velocity = -100.0;
+    let (body, pos) = Body::new(hb);
+    scene.add_element(Element::Generic(Box::new(body)));
+    scene.init_elements();
+    let mut last = pos.get();
+    let mut velocity = [0.0, 0.0];
+    for _ in 0..ticks {
+        scene.update_elements(1.0);
+        velocity = [pos.get()[0] - last[0], pos.get()[1] - last[1]];
+        last = pos.get();
+    }
+    velocity
+}
+
+#[test]
+fn gravity_and_fields_come_from_pom_json() {
+    // 10 ticks of acceleration, after the first two it takes to arrive
+    let close = |a: [f32;2], b: [f32;2]| (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4;
+
+    // sideways world gravity
+    let v = velocity_in(json!({ "gravity": [0.01, 0.0] }), [0.0, 0.0], 1.0, 12);
+    assert!(close(v, [0.1, 0.0]), "velocity {:?}", v);
+
+    // a gravity zone that points up, and the world gravity outside it
+    let zone = json!({ "gravity": [0.0, -0.01], "fields": [{ "kind": "gravity", "vec": [0.0, 0.01], "min": [-10.0, -10.0], "max": [10.0, 10.0] }] });
+    let v = velocity_in(zone.clone(), [0.0, 0.0], 1.0, 12);
+    assert!(close(v, [0.0, 0.1]), "velocity {:?}", v);
+    let v = velocity_in(zone, [50.0, 0.0], 1.0, 12);
+    assert!(close(v, [0.0, -0.1]), "velocity {:?}", v);
+
+    // wind pushes lighter bodies more, and only the layers in its mask
+    let wind = json!({
+        "gravity": [0.0, 0.0],
+        "layers": ["default", "heavy"],
+        "fields": [{ "kind": "wind", "vec": [0.01, 0.0], "min": [-10.0, -10.0], "max": [10.0, 10.0], "mask": ["default"] }]
+    });
+    let light = velocity_in(wind.clone(), [0.0, 0.0], 1.0, 12);
+    let heavy = velocity_in(wind, [0.0, 0.0], 2.0, 12);
+    assert!(close(light, [0.1, 0.0]) && close(heavy, [0.05, 0.0]), "velocities {:?} and {:?}", light, heavy);
+    let masked = json!({
+        "gravity": [0.0, 0.0],
+        "layers": ["default", "heavy"],
+        "fields": [{ "kind": "wind", "vec": [0.01, 0.0], "min": [-10.0, -10.0], "max": [10.0, 10.0], "mask": ["heavy"] }]
+    });
+    assert_eq!(velocity_in(masked, [0.0, 0.0], 1.0, 11), [0.0, 0.0]);
+}
+
+#[test]
+fn contact_summary_reports_ground_walls_and_ceilings() {
+    let mut scene = Scene::default();
+    scene.add_component("pom", Box::new(PhysObjManager::new()));
+    scene.component_manager.access("pom", |pom: &POMComponent| {
+        let sender = pom.new_sender();
+        sender.send(PhysEvent::StaticPO(Uuid::from_u128(1), box_collider([-5.0, -1.0], [10.0, 1.0])));
+        sender.send(PhysEvent::StaticPO(Uuid::from_u128(2), box_collider([2.0, 0.0], [1.0, 5.0])));
+        sender.send(PhysEvent::StaticPO(Uuid::from_u128(3), box_collider([-5.0, 1.5], [3.0, 1.0])));
+    });
+    // walking into the wall, and jumping into the ceiling
+    let mut hb = box_collider([0.0, 0.0], [1.0, 1.0]);
+    hb.delta = [0.05, 0.0];
+    let (walker, _) = Body::new(hb);
+    let walker_summary = walker.summary.clone();
+    scene.add_element(Element::Generic(Box::new(walker)));
+    let mut hb = box_collider([-4.0, 0.0], [1.0, 1.0]);
+    hb.delta = [0.0, 0.3];
+    let (jumper, _) = Body::new(hb);
+    let jumper_summary = jumper.summary.clone();
+    scene.add_element(Element::Generic(Box::new(jumper)));
+    scene.init_elements();
+
+    let mut hit_ceiling = false;
+    let mut at_wall = None;
+    for _ in 0..40 {
+        scene.update_elements(1.0);
+        if walker_summary.get().on_wall() {
+            at_wall = Some(walker_summary.get());
+        }
+        let jumper = jumper_summary.get();
+        if jumper.on_ceiling() {
+            hit_ceiling = true;
+            assert!(!jumper.grounded && jumper.ground.is_none() && jumper.normals() == [[0.0, -1.0]], "{:?}", jumper);
+        }
+    }
+    assert!(hit_ceiling);
+
+    let walker = at_wall.unwrap();
+    assert!(walker.grounded && walker.on_wall() && walker.right && !walker.left && !walker.top, "{:?}", walker);
+    assert_eq!(walker.ground, Some(Uuid::from_u128(1)));
+    assert_eq!(walker.ground_normal, [0.0, 1.0]);
+    assert!(walker.normals().contains(&[0.0, 1.0]) && walker.normals().contains(&[-1.0, 0.0]), "{:?}", walker.normals());
+}
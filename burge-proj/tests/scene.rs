@@ -0,0 +1,403 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use burge_proj::component::{Component, ComponentManager};
+use burge_proj::element::{DefaultCamera, Element, ElementBase};
+use burge_proj::event::{Receiver, Sender};
+use burge_proj::instance::InputEvent;
+use burge_proj::physics::{POMComponent, POSettings, PhysObjManager};
+use burge_proj::scene::{vec2_field, Overlay, SceneChangeError, SceneEvent, SceneFileError, SceneFileErrorKind, SceneManager, SceneProblem, Transition};
+use serde_json::json;
+
+
+/// Element with only a position to save and load
+#[derive(Clone, Default)]
+struct Marker {
+    pos: [f32;2]
+}
+
+impl ElementBase for Marker {
+    fn save(&self) -> serde_json::Value {
+        json!({ "pos": self.pos })
+    }
+    fn try_load(&self, data: &serde_json::Map<String, serde_json::Value>) -> Result<Element, Vec<SceneProblem>> {
+        let mut marker = self.clone();
+        let mut problems = Vec::new();
+        if let Some(pos) = vec2_field(data, "pos", &mut problems) {
+            marker.pos = pos;
+        }
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Element::Generic(Box::new(marker)))
+    }
+}
+
+/// Element with physics settings that can use the names of the scene's pom, like Block in main
+#[derive(Clone, Default)]
+struct Crate {
+    settings: POSettings
+}
+
+impl ElementBase for Crate {
+    fn try_load_in(&self, data: &serde_json::Map<String, serde_json::Value>, components: &ComponentManager) -> Result<Element, Vec<SceneProblem>> {
+        let mut loaded = self.clone();
+        let mut problems = Vec::new();
+        components.access("pom", |pom: &POMComponent| {
+            if let Some(physics) = data.get("physics").and_then(|v| v.as_object()) {
+                match pom.load_settings(&self.settings, physics) {
+                    Ok(settings) => loaded.settings = settings,
+                    Err(errors) => problems.extend(errors.into_iter().map(|p| p.within("physics")))
+                }
+            }
+        });
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Element::Generic(Box::new(loaded)))
+    }
+}
+
+
+fn scene_manager() -> SceneManager {
+    let mut scene_manager = SceneManager::new();
+    scene_manager.json_manager.component_names.insert("pom".to_string(), Box::new(PhysObjManager::new()));
+    scene_manager.json_manager.element_names.insert("default_camera".to_string(), Element::Camera(Box::new(DefaultCamera::new())));
+    scene_manager.json_manager.element_names.insert("marker".to_string(), Element::Generic(Box::new(Marker::default())));
+    scene_manager.json_manager.element_names.insert("crate".to_string(), Element::Generic(Box::new(Crate::default())));
+    scene_manager
+}
+
+const A: &str = "00000000-0000-0000-0000-00000000000a";
+const B: &str = "00000000-0000-0000-0000-00000000000b";
+
+#[test]
+fn saved_scene_loads_back_the_same() {
+    let mut scene_manager = scene_manager();
+    scene_manager.add_scene(json!({
+        "name": "main",
+        "components": [{
+            "name": "pom",
+            "gravity": [0.0, -0.01],
+            "layers": ["player"],
+            "joints": [{ "a": A, "b": B, "kind": "spring", "length": 2.0, "uuid": "00000000-0000-0000-0000-000000000001" }],
+            "fields": [{ "kind": "wind", "min": [0, 0], "max": [4, 4], "vec": [0.1, 0], "mask": "player", "uuid": "00000000-0000-0000-0000-000000000002" }]
+        }],
+        "elements": [
+            { "name": "marker", "uuid": A, "pos": [1, 2] },
+            { "name": "marker", "uuid": B, "pos": [3, 4] }
+        ]
+    })).unwrap();
+
+    let first = scene_manager.save_scene("main").unwrap();
+    scene_manager.add_scene(first.clone()).unwrap();
+    let second = scene_manager.save_scene("main").unwrap();
+    assert_eq!(first, second);
+
+    let pom = &second["components"][0];
+    assert_eq!(pom["name"], "pom");
+    assert_eq!(pom["joints"].as_array().unwrap().len(), 1);
+    assert_eq!(pom["joints"][0]["a"], A);
+    assert_eq!(pom["fields"].as_array().unwrap().len(), 1);
+    assert_eq!(pom["fields"][0]["kind"], "wind");
+
+    let elements = second["elements"].as_array().unwrap();
+    let uuids: Vec<&str> = elements.iter().filter(|e| e["name"] == "marker").map(|e| e["uuid"].as_str().unwrap()).collect();
+    assert_eq!(uuids, vec![A, B]);
+    assert_eq!(elements.iter().find(|e| e["uuid"] == B).unwrap()["pos"], json!([3.0, 4.0]));
+}
+
+#[test]
+fn saved_scene_keeps_one_default_camera() {
+    let mut scene_manager = scene_manager();
+    scene_manager.add_scene(json!({ "name": "main", "components": [], "elements": [] })).unwrap();
+
+    let first = scene_manager.save_scene("main").unwrap();
+    scene_manager.add_scene(first.clone()).unwrap();
+    let second = scene_manager.save_scene("main").unwrap();
+    assert_eq!(first, second);
+
+    let cameras = second["elements"].as_array().unwrap().iter().filter(|e| e["name"] == "default_camera").count();
+    assert_eq!(cameras, 1);
+    assert_eq!(scene_manager.scenes["main"].elements.len(), 2, "camera and the pom's element");
+}
+
+#[test]
+fn invalid_pom_fields_are_reported_with_paths() {
+    let mut scene_manager = scene_manager();
+    let error = scene_manager.add_scene(json!({
+        "name": "main",
+        "components": [{
+            "name": "pom",
+            "cell_size": "big",
+            "joints": [
+                { "a": A, "kind": "rope" },
+                { "a": A, "kind": "elastic" }
+            ],
+            "fields": [{ "kind": "drag", "min": [0, 0], "max": [4, 4], "amount": 0.1, "mask": "water" }],
+            "snapshot": { "dynamics": [{ "uuid": "not a uuid" }] }
+        }]
+    })).unwrap_err();
+
+    let paths: Vec<&str> = error.problems.iter().map(|p| p.path.as_str()).collect();
+    assert_eq!(paths, vec![
+        "components[0].cell_size",
+        "components[0].joints[1].kind",
+        "components[0].fields[0].mask",
+        "components[0].snapshot.dynamics[0].uuid",
+        "components[0].snapshot.dynamics[0].po"
+    ]);
+    assert!(scene_manager.save_scene("main").is_none());
+}
+
+#[test]
+fn element_settings_are_checked_when_the_scene_loads() {
+    let mut scene_manager = scene_manager();
+    scene_manager.add_scene(json!({
+        "name": "main",
+        "components": [{ "name": "pom", "layers": ["ground"] }],
+        "elements": [{ "name": "crate", "physics": { "layer": "ground", "mass": 4, "friction_combine": "max", "one_way": true } }]
+    })).unwrap();
+
+    let error = scene_manager.add_scene(json!({
+        "name": "main",
+        "components": [{ "name": "pom", "layers": ["ground"] }],
+        "elements": [
+            { "name": "default_camera", "pos": [0, "up"], "scale": "big" },
+            { "name": "crate", "physics": { "layer": "water", "mass": "heavy", "friction_combine": "sideways", "one_way": "up" } }
+        ]
+    })).unwrap_err();
+    let problems: Vec<String> = error.problems.iter().map(|p| p.to_string()).collect();
+    assert_eq!(problems, vec![
+        "elements[0].pos: expected [x, y]",
+        "elements[0].scale: expected a number",
+        "elements[1].physics.mass: expected a number",
+        "elements[1].physics.layer: unknown layer \"water\"",
+        "elements[1].physics.friction_combine: unknown combine rule \"sideways\"",
+        "elements[1].physics.one_way: expected true, false or a direction [x, y]"
+    ]);
+}
+
+#[test]
+fn scene_dir_reports_every_bad_file() {
+    let dir = std::env::temp_dir().join(format!("burge-scenes-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a_good.json"), json!({ "elements": [{ "name": "marker", "pos": [1, 2] }] }).to_string()).unwrap();
+    std::fs::write(dir.join("b_bad.json"), json!({ "elements": [{ "name": "marker", "pos": "here" }] }).to_string()).unwrap();
+    std::fs::write(dir.join("c_broken.json"), "{ \"elements\": [").unwrap();
+
+    let mut scene_manager = scene_manager();
+    let errors = scene_manager.add_scenes_from_dir(&dir).unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].path, dir.join("b_bad.json"));
+    match &errors[0].kind {
+        SceneFileErrorKind::Scene(e) => assert_eq!(e.problems[0].path, "elements[0].pos"),
+        kind => panic!("expected scene problems, got {:?}", kind)
+    }
+    assert_eq!(errors[1].path, dir.join("c_broken.json"));
+    assert!(matches!(errors[1].kind, SceneFileErrorKind::Json(_)));
+    assert!(scene_manager.save_scene("a_good").is_none());
+}
+
+#[test]
+fn scene_files_are_named_by_stem_and_errors_name_the_file() {
+    let dir = std::env::temp_dir().join(format!("burge-scene-files-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("level.json"), json!({ "elements": [{ "name": "marker", "pos": [1, 2] }] }).to_string()).unwrap();
+    std::fs::write(dir.join("named.json"), json!({ "name": "boss" }).to_string()).unwrap();
+    std::fs::write(dir.join("broken.json"), "{ \"elements\": [").unwrap();
+    std::fs::write(dir.join("list.json"), "[]").unwrap();
+    std::fs::write(dir.join("bad.json"), json!({ "elements": [{ "name": "marker", "pos": "here" }] }).to_string()).unwrap();
+
+    let mut scene_manager = scene_manager();
+    assert_eq!(scene_manager.add_scene_from_path(dir.join("level.json")).unwrap(), "level");
+    assert_eq!(scene_manager.add_scene_from_path(dir.join("named.json")).unwrap(), "boss");
+    let errors: Vec<SceneFileError> = ["missing.json", "broken.json", "list.json", "bad.json"].iter()
+        .map(|file| scene_manager.add_scene_from_path(dir.join(file)).unwrap_err())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(scene_manager.save_scene("level").is_some() && scene_manager.save_scene("boss").is_some());
+    for (error, file) in errors.iter().zip(["missing.json", "broken.json", "list.json", "bad.json"]) {
+        assert_eq!(error.path, dir.join(file));
+        assert!(error.to_string().contains(&dir.join(file).display().to_string()), "{}", error);
+        assert!(std::error::Error::source(error).is_some());
+        assert!(scene_manager.save_scene(file.trim_end_matches(".json")).is_none());
+    }
+    assert!(matches!(errors[0].kind, SceneFileErrorKind::Read(_)));
+    assert!(matches!(errors[1].kind, SceneFileErrorKind::Json(_)));
+    assert!(errors[2].to_string().ends_with("expected an object"), "{}", errors[2]);
+    assert!(errors[3].to_string().ends_with("elements[0].pos: expected [x, y]"), "{}", errors[3]);
+}
+
+
+/// Counts the key presses it's sent
+#[derive(Clone)]
+struct Listener {
+    input_receiver: Rc<Receiver<InputEvent>>,
+    presses: Rc<Cell<usize>>
+}
+
+impl ElementBase for Listener {
+    fn load(&self, _data: &serde_json::Map<String, serde_json::Value>) -> Element {
+        Element::Generic(Box::new(self.clone()))
+    }
+    fn init(&mut self, _uuid: uuid::Uuid, components: &ComponentManager) {
+        components.access("input", |input: &Sender<InputEvent>| {
+            self.input_receiver = input.new_receiver();
+        });
+    }
+    fn local_update(&mut self, _td: f32) {
+        for e in self.input_receiver.poll() {
+            if let InputEvent::KeyDown(_) = e {
+                self.presses.set(self.presses.get() + 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn paused_scenes_drop_input() {
+    let input = Sender::new();
+    let presses = Rc::new(Cell::new(0));
+    let mut scene_manager = scene_manager();
+    scene_manager.json_manager.component_names.insert("input".to_string(), Box::new(input.clone()));
+    scene_manager.json_manager.element_names.insert("listener".to_string(), Element::Generic(Box::new(Listener { input_receiver: Receiver::new(), presses: presses.clone() })));
+    scene_manager.add_scene(json!({ "name": "game", "components": [{ "name": "input" }], "elements": [{ "name": "listener" }] })).unwrap();
+    scene_manager.add_scene(json!({ "name": "menu", "components": [{ "name": "input" }] })).unwrap();
+    scene_manager.init_scenes();
+    scene_manager.set_scene("game");
+
+    scene_manager.scene_sender().send(SceneEvent::Push("menu".to_string(), Overlay::Pause, Transition::None));
+    scene_manager.update(1.0, 1.0 / 60.0).unwrap();
+    input.send(InputEvent::KeyDown(17));
+    scene_manager.update(1.0, 1.0 / 60.0).unwrap();
+
+    scene_manager.scene_sender().send(SceneEvent::Pop(Transition::None));
+    scene_manager.update(1.0, 1.0 / 60.0).unwrap();
+    scene_manager.update(1.0, 1.0 / 60.0).unwrap();
+    assert_eq!(presses.get(), 0);
+
+    input.send(InputEvent::KeyDown(17));
+    scene_manager.update(1.0, 1.0 / 60.0).unwrap();
+    assert_eq!(presses.get(), 1);
+}
+
+#[test]
+fn only_input_senders_load_as_children() {
+    let sender: Sender<u32> = Sender::new();
+    let receiver = sender.new_receiver();
+    let loaded = Component::load(&sender, &serde_json::Map::new());
+    loaded.to_any().downcast_ref::<Sender<u32>>().unwrap().send(1);
+    assert_eq!(receiver.poll().len(), 1);
+
+    let input: Sender<InputEvent> = Sender::new();
+    let receiver = input.new_receiver();
+    let loaded = Component::load(&input, &serde_json::Map::new());
+    loaded.to_any().downcast_ref::<Sender<InputEvent>>().unwrap().send(InputEvent::KeyDown(17));
+    assert_eq!(receiver.poll().len(), 0);
+}
+
+#[test]
+fn crossfade_push_draws_each_scene_once() {
+    let mut scene_manager = scene_manager();
+    scene_manager.add_scene(json!({ "name": "game" })).unwrap();
+    scene_manager.add_scene(json!({ "name": "menu" })).unwrap();
+    scene_manager.set_scene("game");
+
+    scene_manager.scene_sender().send(SceneEvent::Push("menu".to_string(), Overlay::Pause, Transition::Crossfade(1.0)));
+    scene_manager.update(1.0, 0.0).unwrap();
+    scene_manager.update(1.0, 0.25).unwrap();
+
+    let drawn: Vec<(*const _, f32, bool)> = scene_manager.scenes_to_draw().into_iter().map(|(scene, alpha, running)| (scene as *const _, alpha, running)).collect();
+    assert_eq!(drawn, vec![
+        (&scene_manager.scenes["game"] as *const _, 1.0, false),
+        (&scene_manager.scenes["menu"] as *const _, 0.25, true)
+    ]);
+
+    scene_manager.scene_sender().send(SceneEvent::Pop(Transition::Crossfade(1.0)));
+    scene_manager.update(1.0, 1.0).unwrap();
+    scene_manager.update(1.0, 0.0).unwrap();
+    scene_manager.update(1.0, 0.5).unwrap();
+    let drawn: Vec<(*const _, f32, bool)> = scene_manager.scenes_to_draw().into_iter().map(|(scene, alpha, running)| (scene as *const _, alpha, running)).collect();
+    assert_eq!(drawn, vec![
+        (&scene_manager.scenes["game"] as *const _, 1.0, true),
+        (&scene_manager.scenes["menu"] as *const _, 0.5, false)
+    ]);
+}
+
+#[test]
+fn bad_scene_changes_are_errors() {
+    let mut scene_manager = scene_manager();
+    scene_manager.add_scene(json!({ "name": "game" })).unwrap();
+    scene_manager.add_scene(json!({ "name": "menu" })).unwrap();
+    scene_manager.set_scene("game");
+
+    let sender = scene_manager.scene_sender();
+    sender.send(SceneEvent::Switch("credits".to_string(), Transition::None));
+    sender.send(SceneEvent::Pop(Transition::None));
+    sender.send(SceneEvent::Push("menu".to_string(), Overlay::Pause, Transition::None));
+    sender.send(SceneEvent::Push("game".to_string(), Overlay::Pause, Transition::None));
+    sender.send(SceneEvent::Switch("game".to_string(), Transition::None));
+    assert_eq!(scene_manager.update(1.0, 0.0).unwrap_err(), vec![
+        SceneChangeError::UnknownScene("credits".to_string()),
+        SceneChangeError::NothingToPop,
+        SceneChangeError::AlreadyOnStack("game".to_string()),
+        SceneChangeError::AlreadyOnStack("game".to_string())
+    ]);
+    assert_eq!(scene_manager.current_scene_name(), Some("menu"));
+
+    // replacing the current scene with itself is fine
+    sender.send(SceneEvent::Switch("menu".to_string(), Transition::None));
+    scene_manager.update(1.0, 0.0).unwrap();
+    assert_eq!(scene_manager.visible_scenes().len(), 2);
+}
+
+#[test]
+fn scenes_added_after_init_are_initialised() {
+    let input = Sender::new();
+    let presses = Rc::new(Cell::new(0));
+    let mut scene_manager = scene_manager();
+    scene_manager.json_manager.component_names.insert("input".to_string(), Box::new(input.clone()));
+    scene_manager.json_manager.element_names.insert("listener".to_string(), Element::Generic(Box::new(Listener { input_receiver: Receiver::new(), presses: presses.clone() })));
+    scene_manager.add_scene(json!({ "name": "game" })).unwrap();
+    scene_manager.init_scenes();
+    scene_manager.set_scene("game");
+
+    scene_manager.add_scene(json!({ "name": "level", "components": [{ "name": "input" }], "elements": [{ "name": "listener" }] })).unwrap();
+    scene_manager.scene_sender().send(SceneEvent::Switch("level".to_string(), Transition::None));
+    scene_manager.update(1.0, 0.0).unwrap();
+    input.send(InputEvent::KeyDown(17));
+    scene_manager.update(1.0, 0.0).unwrap();
+    assert_eq!(presses.get(), 1);
+}
+
+#[test]
+fn bad_polygons_in_a_pom_snapshot_are_reported() {
+    let po = |verts: serde_json::Value| json!({ "pos": [0, 0], "shape": [4, 4], "collider": { "kind": "polygon", "verts": verts } });
+    let arrow = json!([[0, 0], [4, 0], [2, 1], [2, 4]]);
+    let nine: Vec<[f32;2]> = (0..9).map(|i| {
+        let angle = i as f32 * std::f32::consts::TAU / 9.0;
+        [angle.cos(), angle.sin()]
+    }).collect();
+
+    let mut scene_manager = scene_manager();
+    let error = scene_manager.add_scene(json!({
+        "name": "main",
+        "components": [{
+            "name": "pom",
+            "snapshot": { "statics": [
+                { "uuid": A, "po": po(arrow) },
+                { "uuid": B, "po": po(json!(nine)) }
+            ] }
+        }]
+    })).unwrap_err();
+
+    let problems: Vec<(&str, &str)> = error.problems.iter().map(|p| (p.path.as_str(), p.message.as_str())).collect();
+    assert_eq!(problems, vec![
+        ("components[0].snapshot.statics[0].po.collider.verts", "not convex"),
+        ("components[0].snapshot.statics[1].po.collider.verts", "at most 8 vertices")
+    ]);
+}